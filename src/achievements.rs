@@ -0,0 +1,163 @@
+use std::collections::BTreeSet;
+
+use bevy::prelude::{Commands, EventReader, EventWriter, Query, Res, ResMut, Resource};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    assets::GameAssets,
+    events::GameEvent,
+    game::GameTime,
+    persistence::{self, Profile},
+    player::PlayerScore,
+    ui::{free_toast_slots, spawn_toast, Toast},
+};
+
+const ACHIEVEMENTS_KEY: &str = "achievements";
+
+const FIRST_POINTS_SCORE: u16 = 10;
+const CENTURION_SCORE: u16 = 100;
+const RESTLESS_WINGS_FLAPS: u32 = 50;
+const BULLSEYE_CENTER_PASSES: u32 = 5;
+const BULLSEYE_CENTER_TOLERANCE: f32 = 4.0;
+const SURVIVOR_SECONDS: f32 = 60.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Achievement {
+    FirstPoints,
+    Centurion,
+    RestlessWings,
+    Bullseye,
+    Survivor,
+}
+
+impl Achievement {
+    pub const ALL: [Achievement; 5] = [
+        Achievement::FirstPoints,
+        Achievement::Centurion,
+        Achievement::RestlessWings,
+        Achievement::Bullseye,
+        Achievement::Survivor,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Achievement::FirstPoints => "Double digits",
+            Achievement::Centurion => "Centurion",
+            Achievement::RestlessWings => "Restless wings",
+            Achievement::Bullseye => "Bullseye",
+            Achievement::Survivor => "Survivor",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Achievement::FirstPoints => "Score 10 points",
+            Achievement::Centurion => "Score 100 points",
+            Achievement::RestlessWings => "50 flaps, no points",
+            Achievement::Bullseye => "5 dead center passes",
+            Achievement::Survivor => "Survive 60 seconds",
+        }
+    }
+
+    fn is_reached(&self, progress: &AchievementProgress, score: u16) -> bool {
+        match self {
+            Achievement::FirstPoints => score >= FIRST_POINTS_SCORE,
+            Achievement::Centurion => score >= CENTURION_SCORE,
+            Achievement::RestlessWings => progress.flaps_since_last_score >= RESTLESS_WINGS_FLAPS,
+            Achievement::Bullseye => progress.center_passes >= BULLSEYE_CENTER_PASSES,
+            Achievement::Survivor => progress.survived_seconds >= SURVIVOR_SECONDS,
+        }
+    }
+}
+
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UnlockedAchievements {
+    pub unlocked: BTreeSet<Achievement>,
+}
+
+/// Run scoped counters the achievements are evaluated against.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct AchievementProgress {
+    pub flaps_since_last_score: u32,
+    pub center_passes: u32,
+    pub survived_seconds: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct AchievementUnlocked(pub Achievement);
+
+pub fn load_unlocked_achievements(mut commands: Commands, profile: Res<Profile>) {
+    commands.insert_resource(persistence::load_or_default::<UnlockedAchievements>(
+        &profile,
+        ACHIEVEMENTS_KEY,
+    ));
+}
+
+pub fn reset_achievement_progress(mut progress: ResMut<AchievementProgress>) {
+    *progress = AchievementProgress::default();
+}
+
+pub fn track_achievement_progress(
+    mut progress: ResMut<AchievementProgress>,
+    mut ev_game: EventReader<GameEvent>,
+    game_time: Res<GameTime>,
+) {
+    progress.survived_seconds += game_time.delta_seconds();
+    for ev in ev_game.iter() {
+        match ev {
+            GameEvent::PlayerFlapped { .. } => progress.flaps_since_last_score += 1,
+            GameEvent::PlayerPassedAnOpening {
+                opening_position,
+                player_position,
+                ..
+            } => {
+                progress.flaps_since_last_score = 0;
+                if (opening_position.y - player_position.y).abs() <= BULLSEYE_CENTER_TOLERANCE {
+                    progress.center_passes += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn evaluate_achievements(
+    mut unlocked: ResMut<UnlockedAchievements>,
+    mut ev_unlocked: EventWriter<AchievementUnlocked>,
+    progress: Res<AchievementProgress>,
+    player_score: Res<PlayerScore>,
+    profile: Res<Profile>,
+) {
+    let reached: Vec<Achievement> = Achievement::ALL
+        .iter()
+        .filter(|a| !unlocked.unlocked.contains(*a) && a.is_reached(&progress, player_score.value))
+        .copied()
+        .collect();
+    if reached.is_empty() {
+        return;
+    }
+    for achievement in reached {
+        unlocked.unlocked.insert(achievement);
+        ev_unlocked.send(AchievementUnlocked(achievement));
+    }
+    persistence::save(&profile, ACHIEVEMENTS_KEY, &*unlocked);
+}
+
+pub fn announce_unlocked_achievements(
+    mut commands: Commands,
+    mut ev_unlocked: EventReader<AchievementUnlocked>,
+    game_assets: Res<GameAssets>,
+    q_toasts: Query<&Toast>,
+) {
+    // the toasts spawned here only take their slot once the commands are applied
+    let slots = free_toast_slots(&q_toasts);
+    for (AchievementUnlocked(achievement), slot) in ev_unlocked.iter().zip(slots) {
+        spawn_toast(
+            &mut commands,
+            game_assets.font.clone(),
+            achievement.title(),
+            achievement.description(),
+            slot,
+        );
+    }
+}
@@ -1,34 +1,141 @@
+use std::collections::HashMap;
+
 use bevy::{
-    prelude::{Component, Deref, DerefMut, Query, Res},
+    prelude::{Component, Query, Res},
     sprite::TextureAtlasSprite,
-    time::{Time, Timer},
 };
+use serde::Deserialize;
+
+use crate::game::GameTime;
+
+pub const CLIP_IDLE: &str = "idle";
+pub const CLIP_FLAP: &str = "flap";
+pub const CLIP_GLIDE: &str = "glide";
+pub const CLIP_HURT: &str = "hurt";
+pub const CLIP_DEAD: &str = "dead";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+pub enum AnimationMode {
+    #[default]
+    Loop,
+    Once,
+}
 
-#[derive(Component)]
-pub struct AnimationIndices {
-    pub first: usize,
-    pub last: usize,
+#[derive(Debug, Clone, Deserialize)]
+pub struct AnimationClip {
+    /// Atlas indices of the frames, in play order.
+    pub frames: Vec<usize>,
+    pub frame_seconds: f32,
+    #[serde(default)]
+    pub mode: AnimationMode,
+    /// Clip played when this one is played once and over.
+    #[serde(default)]
+    pub next: Option<String>,
 }
 
-#[derive(Component, Deref, DerefMut)]
-pub struct AnimationTimer(pub Timer);
+/// Named clips of a sprite sheet. A missing clip falls back to the idle one.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(transparent)]
+pub struct AnimationClips(HashMap<String, AnimationClip>);
+
+impl AnimationClips {
+    pub fn get(&self, name: &str) -> Option<&AnimationClip> {
+        self.0.get(name).or_else(|| self.0.get(CLIP_IDLE))
+    }
+}
+
+/// Plays the [`AnimationClips`] of a sprite sheet, one clip at a time.
+#[derive(Component, Debug, Clone)]
+pub struct Animator {
+    clips: AnimationClips,
+    clip: String,
+    frame: usize,
+    elapsed: f32,
+    finished: bool,
+}
+
+impl Animator {
+    pub fn new(clips: AnimationClips, clip: &str) -> Self {
+        Self {
+            clips,
+            clip: clip.to_string(),
+            frame: 0,
+            elapsed: 0.0,
+            finished: false,
+        }
+    }
+
+    pub fn clip(&self) -> &str {
+        &self.clip
+    }
+
+    /// Atlas index of the frame on display.
+    pub fn atlas_index(&self) -> Option<usize> {
+        self.clips
+            .get(&self.clip)
+            .and_then(|clip| clip.frames.get(self.frame).copied())
+    }
+
+    /// Whether the clip is played once and its last frame is over.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Plays the clip from its first frame, even when it is already playing.
+    pub fn play(&mut self, clip: &str) {
+        self.clip = clip.to_string();
+        self.frame = 0;
+        self.elapsed = 0.0;
+        self.finished = false;
+    }
+
+    /// Plays the clip unless it is already playing.
+    pub fn set(&mut self, clip: &str) {
+        if self.clip != clip {
+            self.play(clip);
+        }
+    }
+
+    fn advance(&mut self, delta_seconds: f32) {
+        if self.finished {
+            return;
+        }
+        self.elapsed += delta_seconds;
+        while let Some(clip) = self.clips.get(&self.clip) {
+            if clip.frame_seconds <= 0.0 || self.elapsed < clip.frame_seconds {
+                return;
+            }
+            self.elapsed -= clip.frame_seconds;
+            if self.frame + 1 < clip.frames.len() {
+                self.frame += 1;
+                continue;
+            }
+            match (clip.mode, clip.next.clone()) {
+                (AnimationMode::Loop, _) => self.frame = 0,
+                (AnimationMode::Once, Some(next)) => {
+                    self.clip = next;
+                    self.frame = 0;
+                }
+                (AnimationMode::Once, None) => {
+                    self.elapsed = 0.0;
+                    self.finished = true;
+                    return;
+                }
+            }
+        }
+    }
+}
 
 pub fn animate_sprite(
-    time: Res<Time>,
-    mut query: Query<(
-        &AnimationIndices,
-        &mut AnimationTimer,
-        &mut TextureAtlasSprite,
-    )>,
+    game_time: Res<GameTime>,
+    mut query: Query<(&mut Animator, &mut TextureAtlasSprite)>,
 ) {
-    for (indices, mut timer, mut sprite) in &mut query {
-        timer.tick(time.delta());
-        if timer.just_finished() {
-            sprite.index = if sprite.index == indices.last {
-                indices.first
-            } else {
-                sprite.index + 1
-            };
+    for (mut animator, mut sprite) in &mut query {
+        animator.advance(game_time.delta_seconds());
+        if let Some(index) = animator.atlas_index() {
+            if sprite.index != index {
+                sprite.index = index;
+            }
         }
     }
 }
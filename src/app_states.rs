@@ -1,10 +1,31 @@
-use bevy::prelude::{Commands, Input, KeyCode, MouseButton, Res};
-use iyes_loopless::state::NextState;
+use bevy::{
+    prelude::{
+        App, Commands, EventReader, EventWriter, Input, KeyCode, MouseButton, Plugin, Res, ResMut,
+        Resource,
+    },
+    time::{Time, Timer, TimerMode},
+};
+use iyes_loopless::{
+    prelude::{AppLooplessStateExt, ConditionSet, IntoConditionalSystem},
+    state::CurrentState,
+};
+
+use crate::{
+    assets::{change_state_to_ingame_when_assets_loaded, load_game_assets},
+    console::{ConsoleCommand, ConsoleCommandInfo, ConsoleLine, RegisterConsoleCommands},
+    events::handle_game_event_player_hit_game_over_collider,
+    schedule::GameStage,
+    transitions::{
+        no_screen_transition, play_screen_transition, start_requested_transitions,
+        ScreenTransition, StateChangeRequest,
+    },
+};
 
 #[derive(Debug, Hash, Clone, Eq, PartialEq)]
 pub enum AppState {
     Launching(LaunchingState),
     InGame(InGameState),
+    Menu(MenuState),
 }
 
 #[derive(Debug, Hash, Clone, Eq, PartialEq)]
@@ -17,14 +38,225 @@ pub enum InGameState {
     Initialization,
     ReadyToStart,
     Playing,
+    GameOver,
+}
+
+#[derive(Debug, Hash, Clone, Eq, PartialEq)]
+pub enum MenuState {
+    Stats,
+    CharacterSelect,
+    Shop,
+    Mutators,
+}
+
+const GAME_OVER_INPUT_DELAY: f32 = 0.6;
+
+const STATE_CONSOLE_COMMANDS: [ConsoleCommandInfo; 1] = [ConsoleCommandInfo {
+    name: "state",
+    args: "<name>",
+    help: "request a state change the game makes itself",
+    completions: &[
+        "ready",
+        "playing",
+        "stats",
+        "characters",
+        "shop",
+        "mutators",
+    ],
+}];
+
+#[derive(Resource)]
+pub struct GameOverInputDelay(Timer);
+
+/// Owns the [`AppState`] machine, the screen transitions and the inputs moving between states.
+/// Must be added before the other game plugins, which register their systems against the states.
+pub struct StatesPlugin;
+
+impl Plugin for StatesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ScreenTransition>()
+            .add_event::<StateChangeRequest>()
+            .add_loopless_state(AppState::Launching(LaunchingState::Loading))
+            .register_console_commands(STATE_CONSOLE_COMMANDS)
+            .add_system(handle_state_console_commands)
+            .add_system(start_requested_transitions)
+            .add_system(play_screen_transition)
+            // LAUNCHING - LOADING
+            .add_startup_system(load_game_assets)
+            .add_system(
+                change_state_to_ingame_when_assets_loaded
+                    .run_in_state(AppState::Launching(LaunchingState::Loading)),
+            )
+            // IN GAME - INITIALIZATION
+            .add_system(
+                change_state_to_ready_to_start
+                    .run_in_state(AppState::InGame(InGameState::Initialization)),
+            )
+            // IN GAME - READY TO START
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(AppState::InGame(InGameState::ReadyToStart))
+                    .with_system(change_state_to_playing_on_input)
+                    .with_system(open_stats_screen_on_input)
+                    .with_system(open_character_select_on_input)
+                    .with_system(open_shop_on_input)
+                    .with_system(open_mutators_on_input)
+                    .into(),
+            )
+            // IN GAME - PLAYING
+            .add_system_set_to_stage(
+                GameStage::Scoring,
+                ConditionSet::new()
+                    .run_in_state(AppState::InGame(InGameState::Playing))
+                    .run_if(no_screen_transition)
+                    .with_system(handle_game_event_player_hit_game_over_collider)
+                    .into(),
+            )
+            // IN GAME - GAME OVER
+            .add_enter_system(
+                AppState::InGame(InGameState::GameOver),
+                start_game_over_input_delay,
+            )
+            .add_system(
+                change_state_to_ready_to_start_on_input
+                    .run_in_state(AppState::InGame(InGameState::GameOver)),
+            )
+            // MENU - STATS
+            .add_system(close_menu_on_input.run_in_state(AppState::Menu(MenuState::Stats)));
+    }
+}
+
+fn change_state_to_ready_to_start(mut ev_state: EventWriter<StateChangeRequest>) {
+    ev_state.send(StateChangeRequest(AppState::InGame(
+        InGameState::ReadyToStart,
+    )));
 }
 
 pub fn change_state_to_playing_on_input(
-    mut commands: Commands,
+    mut ev_state: EventWriter<StateChangeRequest>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Space) || mouse_input.just_pressed(MouseButton::Left) {
+        ev_state.send(StateChangeRequest(AppState::InGame(InGameState::Playing)))
+    }
+}
+
+pub fn start_game_over_input_delay(mut commands: Commands) {
+    commands.insert_resource(GameOverInputDelay(Timer::from_seconds(
+        GAME_OVER_INPUT_DELAY,
+        TimerMode::Once,
+    )));
+}
+
+pub fn change_state_to_ready_to_start_on_input(
+    mut ev_state: EventWriter<StateChangeRequest>,
+    mut input_delay: ResMut<GameOverInputDelay>,
     keyboard_input: Res<Input<KeyCode>>,
     mouse_input: Res<Input<MouseButton>>,
+    time: Res<Time>,
 ) {
+    // ignore the inputs mashed right before the death
+    if !input_delay.0.tick(time.delta()).finished() {
+        return;
+    }
     if keyboard_input.just_pressed(KeyCode::Space) || mouse_input.just_pressed(MouseButton::Left) {
-        commands.insert_resource(NextState(AppState::InGame(InGameState::Playing)))
+        ev_state.send(StateChangeRequest(AppState::InGame(
+            InGameState::ReadyToStart,
+        )))
+    }
+}
+
+pub fn open_stats_screen_on_input(
+    mut ev_state: EventWriter<StateChangeRequest>,
+    keyboard_input: Res<Input<KeyCode>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::S) {
+        ev_state.send(StateChangeRequest(AppState::Menu(MenuState::Stats)))
     }
 }
+
+pub fn open_character_select_on_input(
+    mut ev_state: EventWriter<StateChangeRequest>,
+    keyboard_input: Res<Input<KeyCode>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::C) {
+        ev_state.send(StateChangeRequest(AppState::Menu(
+            MenuState::CharacterSelect,
+        )))
+    }
+}
+
+pub fn open_shop_on_input(
+    mut ev_state: EventWriter<StateChangeRequest>,
+    keyboard_input: Res<Input<KeyCode>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::B) {
+        ev_state.send(StateChangeRequest(AppState::Menu(MenuState::Shop)))
+    }
+}
+
+pub fn open_mutators_on_input(
+    mut ev_state: EventWriter<StateChangeRequest>,
+    keyboard_input: Res<Input<KeyCode>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::X) {
+        ev_state.send(StateChangeRequest(AppState::Menu(MenuState::Mutators)))
+    }
+}
+
+pub fn close_menu_on_input(
+    mut ev_state: EventWriter<StateChangeRequest>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+) {
+    if keyboard_input.any_just_pressed([KeyCode::Escape, KeyCode::Space])
+        || mouse_input.just_pressed(MouseButton::Left)
+    {
+        ev_state.send(StateChangeRequest(AppState::InGame(
+            InGameState::ReadyToStart,
+        )))
+    }
+}
+
+pub fn handle_state_console_commands(
+    mut ev_commands: EventReader<ConsoleCommand>,
+    mut ev_console: EventWriter<ConsoleLine>,
+    mut ev_state: EventWriter<StateChangeRequest>,
+    current_state: Res<CurrentState<AppState>>,
+) {
+    for command in ev_commands.iter().filter(|command| command.name == "state") {
+        let result = command.arg::<String>(0).and_then(|name| {
+            let state = match name.as_str() {
+                "ready" => AppState::InGame(InGameState::ReadyToStart),
+                "playing" => AppState::InGame(InGameState::Playing),
+                "stats" => AppState::Menu(MenuState::Stats),
+                "characters" => AppState::Menu(MenuState::CharacterSelect),
+                "shop" => AppState::Menu(MenuState::Shop),
+                "mutators" => AppState::Menu(MenuState::Mutators),
+                _ => return Err(format!("unknown state `{}`", name)),
+            };
+            if !is_supported_state_change(&current_state.0, &state) {
+                return Err(format!("cannot go to {} from {:?}", name, current_state.0));
+            }
+            ev_state.send(StateChangeRequest(state));
+            Ok(format!("state change to {} requested", name))
+        });
+        ev_console.send(result.into());
+    }
+}
+
+/// Whether the game itself moves between these states, the console requesting no other change:
+/// runs only start from the ready screen and only end by dying.
+fn is_supported_state_change(from: &AppState, to: &AppState) -> bool {
+    matches!(
+        (from, to),
+        (
+            AppState::InGame(InGameState::ReadyToStart),
+            AppState::InGame(InGameState::Playing) | AppState::Menu(_),
+        ) | (
+            AppState::InGame(InGameState::GameOver) | AppState::Menu(_),
+            AppState::InGame(InGameState::ReadyToStart),
+        )
+    )
+}
@@ -1,11 +1,16 @@
 use bevy::{
     asset::LoadState,
-    prelude::{AssetServer, Commands, Handle, Image, Res, Resource},
+    prelude::{
+        AssetServer, Assets, Commands, EventWriter, Handle, Image, Res, ResMut, Resource, Vec2,
+    },
+    sprite::TextureAtlas,
     text::Font,
 };
-use iyes_loopless::state::NextState;
 
-use crate::app_states::{AppState, InGameState};
+use crate::{
+    app_states::{AppState, InGameState},
+    transitions::StateChangeRequest,
+};
 
 #[derive(Resource)]
 pub struct GameAssets {
@@ -13,21 +18,62 @@ pub struct GameAssets {
     pub obstacle_image: Handle<Image>,
     pub ground_image: Handle<Image>,
     pub characters_image: Handle<Image>,
+    pub characters_atlas: Handle<TextureAtlas>,
+    pub digits_image: Handle<Image>,
+    pub digits_atlas: Handle<TextureAtlas>,
+    pub medals_image: Handle<Image>,
+    pub medals_atlas: Handle<TextureAtlas>,
     pub font: Handle<Font>,
 }
 
-pub fn load_game_assets(mut commands: Commands, asset_server: Res<AssetServer>) {
+pub fn load_game_assets(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+) {
+    let digits_image = asset_server.load("sprites/digits.png");
+    let digits_atlas = texture_atlases.add(TextureAtlas::from_grid(
+        digits_image.clone(),
+        Vec2::new(18.0, 18.0),
+        11,
+        1,
+        None,
+        None,
+    ));
+    let characters_image = asset_server.load("sprites/characters.png");
+    let characters_atlas = texture_atlases.add(TextureAtlas::from_grid(
+        characters_image.clone(),
+        Vec2::new(24.0, 24.0),
+        9,
+        3,
+        Some(Vec2::splat(2.0)),
+        None,
+    ));
+    let medals_image = asset_server.load("sprites/medals.png");
+    let medals_atlas = texture_atlases.add(TextureAtlas::from_grid(
+        medals_image.clone(),
+        Vec2::new(24.0, 24.0),
+        4,
+        1,
+        None,
+        None,
+    ));
     commands.insert_resource(GameAssets {
         background_image: asset_server.load("sprites/background.png"),
         obstacle_image: asset_server.load("sprites/obstacle.png"),
         ground_image: asset_server.load("sprites/ground.png"),
-        characters_image: asset_server.load("sprites/characters.png"),
-        font: asset_server.load("fonts/dogica.ttf")
+        characters_image,
+        characters_atlas,
+        digits_image,
+        digits_atlas,
+        medals_image,
+        medals_atlas,
+        font: asset_server.load("fonts/dogica.ttf"),
     })
 }
 
 pub fn change_state_to_ingame_when_assets_loaded(
-    mut commands: Commands,
+    mut ev_state: EventWriter<StateChangeRequest>,
     game_assets: Res<GameAssets>,
     asset_server: Res<AssetServer>,
 ) {
@@ -35,8 +81,12 @@ pub fn change_state_to_ingame_when_assets_loaded(
         && asset_server.get_load_state(game_assets.ground_image.clone()) == LoadState::Loaded
         && asset_server.get_load_state(game_assets.obstacle_image.clone()) == LoadState::Loaded
         && asset_server.get_load_state(game_assets.characters_image.clone()) == LoadState::Loaded
+        && asset_server.get_load_state(game_assets.digits_image.clone()) == LoadState::Loaded
+        && asset_server.get_load_state(game_assets.medals_image.clone()) == LoadState::Loaded
         && asset_server.get_load_state(game_assets.font.clone()) == LoadState::Loaded
     {
-        commands.insert_resource(NextState(AppState::InGame(InGameState::Initialization)))
+        ev_state.send(StateChangeRequest(AppState::InGame(
+            InGameState::Initialization,
+        )))
     }
 }
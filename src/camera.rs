@@ -1,8 +1,349 @@
-use bevy::prelude::{Camera2dBundle, Commands, Component};
+use bevy::{
+    core_pipeline::clear_color::ClearColorConfig,
+    prelude::{
+        App, Camera, Camera2d, Camera2dBundle, Color, Commands, Component, CoreStage, EventReader,
+        Input, IntoSystemDescriptor, KeyCode, OrthographicProjection, Plugin, Query, Res, ResMut,
+        Transform, UVec2, Vec2, Vec3, With, Without,
+    },
+    render::{camera::Viewport, view::RenderLayers},
+    transform::TransformSystem,
+    window::{WindowMode, Windows},
+};
+use iyes_loopless::prelude::AppLooplessStateExt;
+
+use crate::{
+    app_states::{AppState, InGameState},
+    consts::{GAME_HEIGHT, GAME_WIDTH},
+    events::{DeathCause, GameEvent},
+    game::GameTime,
+    motion::MotionSettings,
+    player::Player,
+    schedule::GameStage,
+};
+
+const TRAUMA_DECAY: f32 = 1.4;
+// shake offset at full trauma, in game pixels
+const SHAKE_MAX_OFFSET: f32 = 7.0;
+const SHAKE_FREQUENCY: f32 = 24.0;
+
+// peak of the game over zoom, reached in whole screen pixels per game pixel
+const GAME_OVER_ZOOM: f32 = 1.15;
+const GAME_OVER_ZOOM_SECONDS: f32 = 0.9;
+
+const FOLLOW_RESPONSE: f32 = 6.0;
+
+const FULLSCREEN_TOGGLE_KEY: KeyCode = KeyCode::F11;
+// no entity is on this layer, the letterbox camera only clears the window
+const LETTERBOX_RENDER_LAYER: u8 = 31;
+const LETTERBOX_COLOR: Color = Color::BLACK;
+
+/// The main camera: screen shake, game over zoom and optional vertical follow of the player. The
+/// game keeps its `GAME_WIDTH`x`GAME_HEIGHT` virtual resolution, scaled by the largest integer
+/// factor fitting the window and letterboxed, so pixels stay sharp at any window size. The camera
+/// can be mirrored, [`Readable`] entities are then flipped back for the frame to be drawn.
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(spawn_letterbox_camera)
+            .add_enter_system(AppState::InGame(InGameState::Initialization), spawn_camera)
+            .add_enter_system(AppState::InGame(InGameState::ReadyToStart), reset_camera)
+            .add_enter_system(
+                AppState::InGame(InGameState::GameOver),
+                zoom_camera_on_game_over,
+            )
+            .add_system_to_stage(GameStage::Ui, add_camera_trauma_on_hit)
+            .add_system_to_stage(GameStage::Ui, update_camera)
+            .add_system(fit_camera_to_window)
+            .add_system(toggle_fullscreen_on_input)
+            .add_system_to_stage(CoreStage::First, unflip_readable)
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                flip_readable.before(TransformSystem::TransformPropagate),
+            );
+    }
+}
 
 #[derive(Component)]
 pub struct MainCamera;
 
+/// Vertical bounds the camera center stays within when following the player.
+#[derive(Debug, Clone, Copy)]
+pub struct CameraFollow {
+    pub min_y: f32,
+    pub max_y: f32,
+}
+
+#[derive(Component, Debug, Clone, Default)]
+pub struct CameraController {
+    /// From 0 to 1, the shake grows with its square and it decays over time.
+    pub trauma: f32,
+    /// Follows the player vertically when set, for levels taller than the screen.
+    pub follow: Option<CameraFollow>,
+    /// Shows the world flipped horizontally.
+    pub mirrored: bool,
+    center: Vec2,
+    zoom_elapsed: Option<f32>,
+    time: f32,
+    /// Screen pixels per game pixel, fitted to the window.
+    pixel_factor: u32,
+}
+
+impl CameraController {
+    pub fn add_trauma(&mut self, trauma: f32) {
+        self.trauma = (self.trauma + trauma).min(1.0);
+    }
+
+    /// Zooms in and back out over [`GAME_OVER_ZOOM_SECONDS`].
+    pub fn pulse_zoom(&mut self) {
+        self.zoom_elapsed = Some(0.0);
+    }
+
+    /// Steps through whole screen pixels per game pixel, so that pixels stay even while zoomed,
+    /// at least one step up at the peak.
+    fn zoom(&self) -> f32 {
+        let curve = match self.zoom_elapsed {
+            Some(elapsed) => {
+                let progress = (elapsed / GAME_OVER_ZOOM_SECONDS).min(1.0);
+                // up fast, back down slowly
+                (progress * std::f32::consts::PI).sin().powf(0.6)
+            }
+            None => return 1.0,
+        };
+        let factor = self.pixel_factor.max(1) as f32;
+        let peak_steps = ((GAME_OVER_ZOOM - 1.0) * factor).round().max(1.0);
+        1.0 + (curve * peak_steps).round() / factor
+    }
+}
+
+/// Text and ui staying readable while the camera is mirrored: flipped in place, and moved back to
+/// its place on screen when anchored to the screen. Systems always see the unflipped transform.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Readable {
+    screen_space: bool,
+    flipped: bool,
+}
+
+impl Readable {
+    pub fn screen_space() -> Self {
+        Self {
+            screen_space: true,
+            flipped: false,
+        }
+    }
+
+    pub fn in_place() -> Self {
+        Self {
+            screen_space: false,
+            flipped: false,
+        }
+    }
+
+    fn flip(&mut self, t: &mut Transform) {
+        self.flipped = !self.flipped;
+        t.scale.x = -t.scale.x;
+        if self.screen_space {
+            t.translation.x = -t.translation.x;
+        }
+    }
+}
+
+fn death_trauma(cause: DeathCause) -> f32 {
+    match cause {
+        DeathCause::TopBound => 0.3,
+        DeathCause::Ground => 0.55,
+        DeathCause::Pipe => 0.7,
+        DeathCause::Bird => 0.5,
+        DeathCause::Rock => 0.75,
+    }
+}
+
 pub fn spawn_camera(mut commands: Commands) {
-    commands.spawn((Camera2dBundle::default(), MainCamera));
+    commands.spawn((
+        Camera2dBundle::default(),
+        MainCamera,
+        CameraController::default(),
+    ));
+}
+
+/// Clears the whole window, around the viewport of the main camera.
+pub fn spawn_letterbox_camera(mut commands: Commands) {
+    commands.spawn((
+        Camera2dBundle {
+            camera: Camera {
+                priority: -1,
+                ..Default::default()
+            },
+            camera_2d: Camera2d {
+                clear_color: ClearColorConfig::Custom(LETTERBOX_COLOR),
+            },
+            ..Default::default()
+        },
+        RenderLayers::layer(LETTERBOX_RENDER_LAYER),
+    ));
+}
+
+pub fn reset_camera(mut q_camera: Query<&mut CameraController, With<MainCamera>>) {
+    if let Ok(mut controller) = q_camera.get_single_mut() {
+        *controller = CameraController {
+            follow: controller.follow,
+            mirrored: controller.mirrored,
+            pixel_factor: controller.pixel_factor,
+            ..Default::default()
+        };
+    }
+}
+
+pub fn zoom_camera_on_game_over(mut q_camera: Query<&mut CameraController, With<MainCamera>>) {
+    if let Ok(mut controller) = q_camera.get_single_mut() {
+        controller.pulse_zoom();
+    }
+}
+
+pub fn add_camera_trauma_on_hit(
+    mut ev_game: EventReader<GameEvent>,
+    mut q_camera: Query<&mut CameraController, With<MainCamera>>,
+) {
+    for ev in ev_game.iter() {
+        if let GameEvent::PlayerHitGameOverCollider { cause, .. } = ev {
+            if let Ok(mut controller) = q_camera.get_single_mut() {
+                controller.add_trauma(death_trauma(*cause));
+            }
+        }
+    }
+}
+
+pub fn update_camera(
+    mut q_camera: Query<(&mut CameraController, &mut Transform), With<MainCamera>>,
+    q_player: Query<&Transform, (With<Player>, Without<MainCamera>)>,
+    motion_settings: Res<MotionSettings>,
+    game_time: Res<GameTime>,
+) {
+    let (mut controller, mut t) = match q_camera.get_single_mut() {
+        Ok(camera) => camera,
+        Err(_) => return,
+    };
+    let dt = game_time.delta_seconds();
+    let player_position = q_player
+        .get_single()
+        .map_or(Vec2::ZERO, |t| t.translation.truncate());
+
+    controller.time += dt;
+    controller.trauma = (controller.trauma - TRAUMA_DECAY * dt).max(0.0);
+    if let Some(elapsed) = controller.zoom_elapsed.as_mut() {
+        *elapsed += dt;
+    }
+    if let Some(follow) = controller.follow {
+        let target = player_position.y.clamp(follow.min_y, follow.max_y);
+        controller.center.y +=
+            (target - controller.center.y) * (1.0 - (-FOLLOW_RESPONSE * dt).exp());
+    }
+
+    let mut center = controller.center;
+    let mut zoom = 1.0;
+    if !motion_settings.reduced_motion {
+        let shake = controller.trauma * controller.trauma * SHAKE_MAX_OFFSET;
+        let time = controller.time * SHAKE_FREQUENCY;
+        // a few incommensurate sines make a cheap noise
+        center += Vec2::new(
+            (time * 1.0).sin() * 0.6 + (time * 2.3 + 1.7).sin() * 0.4,
+            (time * 1.3 + 0.5).sin() * 0.6 + (time * 2.9 + 2.3).sin() * 0.4,
+        ) * shake;
+        zoom = controller.zoom();
+        // keeps the player at the same place on screen while zooming
+        center += (player_position - center) * (1.0 - 1.0 / zoom);
+    }
+
+    // whole game pixels, sprites never land between two screen pixels
+    t.translation.x = center.x.round();
+    t.translation.y = center.y.round();
+    let flip = if controller.mirrored { -1.0 } else { 1.0 };
+    t.scale = Vec3::new(flip / zoom, 1.0 / zoom, 1.0);
+}
+
+pub fn unflip_readable(mut q_readable: Query<(&mut Readable, &mut Transform)>) {
+    for (mut readable, mut t) in q_readable.iter_mut() {
+        if readable.flipped {
+            readable.flip(&mut t);
+        }
+    }
+}
+
+pub fn flip_readable(
+    mut q_readable: Query<(&mut Readable, &mut Transform)>,
+    q_camera: Query<&CameraController, With<MainCamera>>,
+) {
+    if !q_camera.get_single().map_or(false, |c| c.mirrored) {
+        return;
+    }
+    for (mut readable, mut t) in q_readable.iter_mut() {
+        if !readable.flipped {
+            readable.flip(&mut t);
+        }
+    }
+}
+
+/// Scales the game by the largest integer factor of physical pixels fitting the window, and
+/// centers it in the window.
+pub fn fit_camera_to_window(
+    windows: Res<Windows>,
+    mut q_camera: Query<
+        (
+            &mut Camera,
+            &mut OrthographicProjection,
+            &mut CameraController,
+        ),
+        With<MainCamera>,
+    >,
+) {
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let window_size = UVec2::new(window.physical_width(), window.physical_height());
+    let factor = (window_size.x as f32 / GAME_WIDTH)
+        .min(window_size.y as f32 / GAME_HEIGHT)
+        .floor()
+        .max(1.0);
+    let size = (Vec2::new(GAME_WIDTH, GAME_HEIGHT) * factor).as_uvec2();
+    let viewport = Viewport {
+        physical_position: UVec2::new(
+            window_size.x.saturating_sub(size.x) / 2,
+            window_size.y.saturating_sub(size.y) / 2,
+        ),
+        physical_size: size.min(window_size),
+        ..Default::default()
+    };
+    // the projection is in logical pixels
+    let scale = window.scale_factor() as f32 / factor;
+    for (mut camera, mut projection, mut controller) in q_camera.iter_mut() {
+        let unchanged = camera.viewport.as_ref().map_or(false, |current| {
+            current.physical_position == viewport.physical_position
+                && current.physical_size == viewport.physical_size
+        });
+        if !unchanged {
+            camera.viewport = Some(viewport.clone());
+        }
+        if projection.scale != scale {
+            projection.scale = scale;
+        }
+        if controller.pixel_factor != factor as u32 {
+            controller.pixel_factor = factor as u32;
+        }
+    }
+}
+
+pub fn toggle_fullscreen_on_input(
+    mut windows: ResMut<Windows>,
+    keyboard_input: Res<Input<KeyCode>>,
+) {
+    if !keyboard_input.just_pressed(FULLSCREEN_TOGGLE_KEY) {
+        return;
+    }
+    if let Some(window) = windows.get_primary_mut() {
+        window.set_mode(match window.mode() {
+            WindowMode::Windowed => WindowMode::BorderlessFullscreen,
+            _ => WindowMode::Windowed,
+        });
+    }
 }
@@ -0,0 +1,179 @@
+use bevy::prelude::{Commands, EventWriter, Input, KeyCode, MouseButton, Res, ResMut, Resource};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    animations::AnimationClips,
+    app_states::{AppState, InGameState},
+    coins::Wallet,
+    consts::JUMP_IMPULSE_VALUE,
+    persistence::{self, Profile},
+    shop::ShopCatalog,
+    transitions::StateChangeRequest,
+};
+
+const CHARACTERS_DATA: &str = include_str!("../assets/sprites/characters.ron");
+const SELECTED_CHARACTER_KEY: &str = "character";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CharacterDefinition {
+    pub id: String,
+    pub name: String,
+    /// Animations of the character in `characters.png`.
+    pub clips: AnimationClips,
+    /// Radius of the collider, when `sprites/hitboxes.ron` has no shape for the character.
+    pub collider_radius: f32,
+    #[serde(default)]
+    gravity_scale: Option<f32>,
+    #[serde(default)]
+    jump_impulse: Option<f32>,
+}
+
+impl CharacterDefinition {
+    pub fn gravity_scale(&self) -> f32 {
+        self.gravity_scale.unwrap_or(1.0)
+    }
+
+    pub fn jump_impulse(&self) -> f32 {
+        self.jump_impulse.unwrap_or(JUMP_IMPULSE_VALUE)
+    }
+}
+
+#[derive(Deserialize)]
+struct CharactersData {
+    characters: Vec<CharacterDefinition>,
+}
+
+/// The playable characters, defined in `sprites/characters.ron`.
+#[derive(Resource, Debug, Clone)]
+pub struct CharacterRoster {
+    characters: Vec<CharacterDefinition>,
+}
+
+impl Default for CharacterRoster {
+    fn default() -> Self {
+        let data: CharactersData =
+            ron::from_str(CHARACTERS_DATA).expect("the characters data file is invalid");
+        assert!(
+            !data.characters.is_empty(),
+            "the characters data file has no character"
+        );
+        Self {
+            characters: data.characters,
+        }
+    }
+}
+
+impl CharacterRoster {
+    pub fn characters(&self) -> &[CharacterDefinition] {
+        &self.characters
+    }
+
+    pub fn index_of(&self, id: &str) -> Option<usize> {
+        self.characters.iter().position(|c| c.id == id)
+    }
+
+    /// The character with this id, or the first one of the roster.
+    pub fn get_or_first(&self, id: &str) -> &CharacterDefinition {
+        &self.characters[self.index_of(id).unwrap_or(0)]
+    }
+}
+
+/// The character the player flies with, persisted under the current [`Profile`].
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SelectedCharacter {
+    pub id: String,
+}
+
+impl SelectedCharacter {
+    pub fn save(&self, profile: &Profile) {
+        persistence::save(profile, SELECTED_CHARACTER_KEY, self);
+    }
+}
+
+/// Roster index highlighted on the character select screen.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct CharacterSelectCursor(pub usize);
+
+pub fn load_selected_character(mut commands: Commands, profile: Res<Profile>) {
+    commands.insert_resource(persistence::load_or_default::<SelectedCharacter>(
+        &profile,
+        SELECTED_CHARACTER_KEY,
+    ));
+}
+
+pub fn start_character_select(
+    mut commands: Commands,
+    roster: Res<CharacterRoster>,
+    selected: Res<SelectedCharacter>,
+) {
+    commands.insert_resource(CharacterSelectCursor(
+        roster.index_of(&selected.id).unwrap_or(0),
+    ));
+}
+
+pub fn browse_characters_on_input(
+    mut ev_state: EventWriter<StateChangeRequest>,
+    mut cursor: ResMut<CharacterSelectCursor>,
+    mut selected: ResMut<SelectedCharacter>,
+    roster: Res<CharacterRoster>,
+    catalog: Res<ShopCatalog>,
+    wallet: Res<Wallet>,
+    profile: Res<Profile>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        ev_state.send(StateChangeRequest(AppState::InGame(
+            InGameState::ReadyToStart,
+        )));
+        return;
+    }
+    let count = roster.characters().len();
+    if count == 0 {
+        return;
+    }
+    if keyboard_input.any_just_pressed([KeyCode::Left, KeyCode::A]) {
+        cursor.0 = (cursor.0 + count - 1) % count;
+    }
+    if keyboard_input.any_just_pressed([KeyCode::Right, KeyCode::D]) {
+        cursor.0 = (cursor.0 + 1) % count;
+    }
+
+    let character = &roster.characters()[cursor.0];
+    // locked characters are bought in the shop
+    let confirmed = (keyboard_input.any_just_pressed([KeyCode::Space, KeyCode::Return])
+        || mouse_input.just_pressed(MouseButton::Left))
+        && catalog.locked_price(&character.id, &wallet).is_none();
+    if confirmed {
+        selected.id = character.id.clone();
+        selected.save(&profile);
+        ev_state.send(StateChangeRequest(AppState::InGame(
+            InGameState::ReadyToStart,
+        )));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn roster_is_not_empty() {
+        assert!(!CharacterRoster::default().characters().is_empty());
+    }
+
+    #[test]
+    fn character_ids_are_unique() {
+        let roster = CharacterRoster::default();
+        let mut ids = HashSet::new();
+        for character in roster.characters() {
+            assert!(
+                ids.insert(&character.id),
+                "{} is listed twice",
+                character.id
+            );
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use std::collections::BTreeSet;
+
+use bevy::{
+    prelude::{
+        App, Color, Commands, Component, Entity, EventReader, Plugin, Query, Res, ResMut, Resource,
+        Transform, Vec2, With,
+    },
+    sprite::{Sprite, SpriteBundle},
+};
+use iyes_loopless::prelude::{AppLooplessStateExt, ConditionSet};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_states::{AppState, InGameState},
+    consts::{COLLISION_GROUP_PICKUP, COLLISION_GROUP_PLAYER, GAME_WIDTH},
+    game::{not_practicing, HorizontalMove},
+    particles::spawn_particle_burst,
+    persistence::{self, Profile},
+    physics::{ContactEvent, Hitbox, PhysicsBody},
+    player::Player,
+    powerups::Magnetic,
+    rewind::DroppedOnRewind,
+    schedule::GameStage,
+    transitions::no_screen_transition,
+};
+
+const WALLET_KEY: &str = "wallet";
+
+const COIN_ARC_COUNT: usize = 5;
+const COIN_ARC_SPACING: f32 = 16.0;
+// rise of the middle coin over the outer ones, all of them stay within the opening
+const COIN_ARC_HEIGHT: f32 = 14.0;
+const COIN_SIZE: f32 = 6.0;
+const COIN_Z: f32 = 4.0;
+const COIN_COLOR: Color = Color::rgb(1.0, 0.82, 0.25);
+
+const EFFECT_GLINT: &str = "glint";
+
+/// Coins placed in arcs through the openings, counted during a run and added to the [`Wallet`]
+/// of the [`Profile`] at its end. Practice runs earn nothing.
+pub struct CoinsPlugin;
+
+impl Plugin for CoinsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Profile>()
+            .init_resource::<RunCoins>()
+            .init_resource::<Wallet>()
+            .add_startup_system(load_wallet)
+            .add_enter_system(AppState::InGame(InGameState::ReadyToStart), reset_coins)
+            .add_system_set_to_stage(
+                GameStage::Movement,
+                ConditionSet::new()
+                    .run_in_state(AppState::InGame(InGameState::Playing))
+                    .run_if(no_screen_transition)
+                    .with_system(despawn_passed_coins)
+                    .into(),
+            )
+            .add_system_set_to_stage(
+                GameStage::CollisionDispatch,
+                ConditionSet::new()
+                    .run_in_state(AppState::InGame(InGameState::Playing))
+                    .run_if(no_screen_transition)
+                    .with_system(collect_coins)
+                    .into(),
+            )
+            .add_exit_system_set(
+                AppState::InGame(InGameState::Playing),
+                ConditionSet::new()
+                    .run_if(not_practicing)
+                    .with_system(bank_run_coins)
+                    .into(),
+            );
+    }
+}
+
+#[derive(Component)]
+pub struct Coin;
+
+/// Coins collected during the run.
+#[derive(Resource, Default, Debug, Clone, Copy)]
+pub struct RunCoins {
+    pub value: u32,
+}
+
+/// Coins banked over every run and the shop items bought with them, persisted under the current
+/// [`Profile`].
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Wallet {
+    pub coins: u32,
+    pub unlocked: BTreeSet<String>,
+}
+
+impl Wallet {
+    pub fn owns(&self, id: &str) -> bool {
+        self.unlocked.contains(id)
+    }
+
+    /// Takes `price` coins to unlock the item, `false` when the wallet is short.
+    pub fn buy(&mut self, id: &str, price: u32) -> bool {
+        if self.coins < price {
+            return false;
+        }
+        self.coins -= price;
+        self.unlocked.insert(id.to_string());
+        true
+    }
+
+    pub fn save(&self, profile: &Profile) {
+        persistence::save(profile, WALLET_KEY, self);
+    }
+}
+
+pub fn load_wallet(mut commands: Commands, profile: Res<Profile>) {
+    commands.insert_resource(persistence::load_or_default::<Wallet>(&profile, WALLET_KEY));
+}
+
+/// Spawns an arc of coins through the opening centered on `opening_center`, the middle coin
+/// right between the pipes.
+pub fn spawn_coin_arc(commands: &mut Commands, opening_center: Vec2) {
+    let half_count = (COIN_ARC_COUNT - 1) as f32 / 2.0;
+    for i in 0..COIN_ARC_COUNT {
+        let t = i as f32 / half_count - 1.0;
+        let position = opening_center
+            + Vec2::new(
+                t * half_count * COIN_ARC_SPACING,
+                COIN_ARC_HEIGHT * (0.5 - t * t),
+            );
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: COIN_COLOR,
+                    custom_size: Some(Vec2::splat(COIN_SIZE)),
+                    ..Default::default()
+                },
+                transform: Transform::from_translation(position.extend(COIN_Z)),
+                ..Default::default()
+            },
+            HorizontalMove { factor: 1.0 },
+            PhysicsBody::kinematic(Hitbox::Circle {
+                radius: COIN_SIZE / 2.0,
+            })
+            .sensor()
+            .with_groups(COLLISION_GROUP_PICKUP, COLLISION_GROUP_PLAYER),
+            Coin,
+            Magnetic,
+            DroppedOnRewind,
+        ));
+    }
+}
+
+pub fn reset_coins(
+    mut commands: Commands,
+    mut run_coins: ResMut<RunCoins>,
+    q_coins: Query<Entity, With<Coin>>,
+) {
+    for e in q_coins.iter() {
+        commands.entity(e).despawn();
+    }
+    *run_coins = RunCoins::default();
+}
+
+pub fn despawn_passed_coins(
+    mut commands: Commands,
+    q_coins: Query<(Entity, &Transform), With<Coin>>,
+) {
+    for (e, t) in q_coins.iter() {
+        if t.translation.x < -GAME_WIDTH / 2.0 - COIN_SIZE {
+            commands.entity(e).despawn();
+        }
+    }
+}
+
+pub fn collect_coins(
+    mut commands: Commands,
+    mut ev_contacts: EventReader<ContactEvent>,
+    mut run_coins: ResMut<RunCoins>,
+    q_player: Query<(), With<Player>>,
+    q_coins: Query<&Transform, With<Coin>>,
+) {
+    for contact in ev_contacts.iter() {
+        let (ent_a, ent_b) = match *contact {
+            ContactEvent::Started(ent_a, ent_b) => (ent_a, ent_b),
+            ContactEvent::Stopped(..) => continue,
+        };
+        let coin = if q_player.contains(ent_a) {
+            ent_b
+        } else if q_player.contains(ent_b) {
+            ent_a
+        } else {
+            continue;
+        };
+        if let Ok(t) = q_coins.get(coin) {
+            commands.entity(coin).despawn();
+            spawn_particle_burst(&mut commands, EFFECT_GLINT, t.translation.truncate());
+            run_coins.value += 1;
+        }
+    }
+}
+
+pub fn bank_run_coins(mut wallet: ResMut<Wallet>, run_coins: Res<RunCoins>, profile: Res<Profile>) {
+    if run_coins.value == 0 {
+        return;
+    }
+    wallet.coins += run_coins.value;
+    wallet.save(&profile);
+}
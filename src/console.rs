@@ -0,0 +1,420 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    str::FromStr,
+};
+
+use bevy::{
+    input::InputSystem,
+    prelude::{
+        App, Color, Commands, Component, CoreStage, Entity, EventReader, EventWriter, Input,
+        IntoSystemDescriptor, KeyCode, MouseButton, Plugin, Query, Res, ResMut, Resource,
+        Transform, Vec2, Vec3, With,
+    },
+    sprite::{Sprite, SpriteBundle},
+    text::{Text, Text2dBundle, TextAlignment, TextStyle},
+    window::ReceivedCharacter,
+};
+
+use crate::{
+    assets::GameAssets,
+    camera::Readable,
+    consts::{GAME_HEIGHT, GAME_WIDTH},
+};
+
+const CONSOLE_TOGGLE_KEY: KeyCode = KeyCode::Grave;
+const CONSOLE_TOGGLE_CHARS: [char; 2] = ['`', '~'];
+
+const CONSOLE_Z: f32 = 950.0;
+const CONSOLE_HEIGHT: f32 = 128.0;
+const CONSOLE_PADDING: f32 = 6.0;
+const CONSOLE_FONT_SIZE: f32 = 8.0;
+const CONSOLE_COLOR: Color = Color::rgba(0.1, 0.11, 0.15, 0.9);
+const CONSOLE_PROMPT: &str = "> ";
+
+const CONSOLE_LOG_LINES: usize = 11;
+const CONSOLE_HISTORY_SIZE: usize = 32;
+
+const CONSOLE_COMMANDS: [ConsoleCommandInfo; 2] = [
+    ConsoleCommandInfo {
+        name: "help",
+        args: "",
+        help: "list the commands",
+        completions: &[],
+    },
+    ConsoleCommandInfo {
+        name: "clear",
+        args: "",
+        help: "clear the console",
+        completions: &[],
+    },
+];
+
+/// A toggleable overlay submitting commands to the plugins that registered them with
+/// [`RegisterConsoleCommands`].
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Console>()
+            .register_console_commands(CONSOLE_COMMANDS)
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                update_console_input.after(InputSystem),
+            )
+            .add_system(handle_console_commands)
+            .add_system_to_stage(CoreStage::PostUpdate, print_console_lines)
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                sync_console_overlay.after(print_console_lines),
+            );
+    }
+}
+
+/// A command accepted by the console.
+#[derive(Debug, Clone, Copy)]
+pub struct ConsoleCommandInfo {
+    pub name: &'static str,
+    /// Arguments, as shown by `help`.
+    pub args: &'static str,
+    pub help: &'static str,
+    /// Values the first argument autocompletes to.
+    pub completions: &'static [&'static str],
+}
+
+/// Registry of the commands the console accepts, by name.
+#[derive(Resource, Default)]
+pub struct ConsoleCommands(BTreeMap<&'static str, ConsoleCommandInfo>);
+
+impl ConsoleCommands {
+    pub fn get(&self, name: &str) -> Option<&ConsoleCommandInfo> {
+        self.0.get(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ConsoleCommandInfo> {
+        self.0.values()
+    }
+}
+
+pub trait RegisterConsoleCommands {
+    /// Makes the console accept commands, submitted as [`ConsoleCommand`] events to the systems
+    /// of the registering plugin.
+    fn register_console_commands(
+        &mut self,
+        commands: impl IntoIterator<Item = ConsoleCommandInfo>,
+    ) -> &mut Self;
+}
+
+impl RegisterConsoleCommands for App {
+    fn register_console_commands(
+        &mut self,
+        commands: impl IntoIterator<Item = ConsoleCommandInfo>,
+    ) -> &mut Self {
+        self.add_event::<ConsoleCommand>()
+            .add_event::<ConsoleLine>()
+            .init_resource::<ConsoleCommands>();
+        let mut registry = self.world.resource_mut::<ConsoleCommands>();
+        for info in commands {
+            registry.0.insert(info.name, info);
+        }
+        self
+    }
+}
+
+/// A submitted command, its name being registered.
+#[derive(Debug, Clone)]
+pub struct ConsoleCommand {
+    pub name: String,
+    pub args: Vec<String>,
+}
+
+impl ConsoleCommand {
+    pub fn arg<T: FromStr>(&self, index: usize) -> Result<T, String> {
+        let arg = self
+            .args
+            .get(index)
+            .ok_or_else(|| format!("{}: missing argument {}", self.name, index + 1))?;
+        arg.parse()
+            .map_err(|_| format!("{}: invalid argument `{}`", self.name, arg))
+    }
+}
+
+/// A line printed in the console, usually the outcome of a command.
+#[derive(Debug, Clone)]
+pub struct ConsoleLine(pub String);
+
+impl From<Result<String, String>> for ConsoleLine {
+    fn from(result: Result<String, String>) -> Self {
+        match result {
+            Ok(line) => ConsoleLine(line),
+            Err(error) => ConsoleLine(format!("error: {}", error)),
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct Console {
+    open: bool,
+    input: String,
+    history: Vec<String>,
+    history_cursor: Option<usize>,
+    log: VecDeque<String>,
+}
+
+impl Console {
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    fn print(&mut self, line: String) {
+        self.log.push_back(line);
+        while self.log.len() > CONSOLE_LOG_LINES {
+            self.log.pop_front();
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct ConsoleOverlay;
+
+#[derive(Component)]
+pub struct ConsoleText;
+
+/// Edits and submits the console input. The keyboard and mouse are consumed while the console is
+/// open, so typing or clicking never reaches the game.
+pub fn update_console_input(
+    mut console: ResMut<Console>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut mouse_input: ResMut<Input<MouseButton>>,
+    mut ev_chars: EventReader<ReceivedCharacter>,
+    mut ev_commands: EventWriter<ConsoleCommand>,
+    mut ev_console: EventWriter<ConsoleLine>,
+    commands: Res<ConsoleCommands>,
+) {
+    if keyboard_input.just_pressed(CONSOLE_TOGGLE_KEY) {
+        console.open = !console.open;
+    }
+    if !console.open {
+        ev_chars.clear();
+        return;
+    }
+
+    for ev in ev_chars.iter() {
+        if !ev.char.is_control() && !CONSOLE_TOGGLE_CHARS.contains(&ev.char) {
+            console.input.push(ev.char);
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        console.input.pop();
+    }
+    if keyboard_input.just_pressed(KeyCode::Up) {
+        browse_history(&mut console, true);
+    }
+    if keyboard_input.just_pressed(KeyCode::Down) {
+        browse_history(&mut console, false);
+    }
+    if keyboard_input.just_pressed(KeyCode::Tab) {
+        let candidates = autocomplete(&mut console.input, &commands);
+        if candidates.len() > 1 {
+            console.print(candidates.join(" "));
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::Return) {
+        let line = std::mem::take(&mut console.input);
+        submit(
+            &mut console,
+            line,
+            &commands,
+            &mut ev_commands,
+            &mut ev_console,
+        );
+    }
+    keyboard_input.reset_all();
+    mouse_input.reset_all();
+}
+
+fn browse_history(console: &mut Console, older: bool) {
+    if console.history.is_empty() {
+        return;
+    }
+    let last = console.history.len() - 1;
+    console.history_cursor = match (console.history_cursor, older) {
+        (None, true) => Some(last),
+        (None, false) => None,
+        (Some(0), true) => Some(0),
+        (Some(cursor), true) => Some(cursor - 1),
+        (Some(cursor), false) if cursor < last => Some(cursor + 1),
+        (Some(_), false) => None,
+    };
+    console.input = console
+        .history_cursor
+        .map(|cursor| console.history[cursor].clone())
+        .unwrap_or_default();
+}
+
+/// Completes the word being typed, a command name or its first argument, as far as all the
+/// matching candidates agree, and returns these candidates.
+fn autocomplete(input: &mut String, commands: &ConsoleCommands) -> Vec<&'static str> {
+    let words: Vec<&str> = input.split(' ').collect();
+    let (prefix, candidates): (&str, Vec<&'static str>) = match words.as_slice() {
+        [name] => (
+            *name,
+            commands
+                .iter()
+                .map(|info| info.name)
+                .filter(|candidate| candidate.starts_with(name))
+                .collect(),
+        ),
+        [name, arg] => (
+            *arg,
+            commands
+                .get(name)
+                .map(|info| info.completions)
+                .unwrap_or_default()
+                .iter()
+                .copied()
+                .filter(|candidate| candidate.starts_with(arg))
+                .collect(),
+        ),
+        _ => return Vec::new(),
+    };
+    let completed = match candidates.as_slice() {
+        [] => return Vec::new(),
+        [candidate] => format!("{} ", candidate),
+        [first, others @ ..] => others.iter().fold(first.to_string(), |common, candidate| {
+            common
+                .chars()
+                .zip(candidate.chars())
+                .take_while(|(a, b)| a == b)
+                .map(|(a, _)| a)
+                .collect()
+        }),
+    };
+    let kept = input.len() - prefix.len();
+    input.truncate(kept);
+    input.push_str(&completed);
+    candidates
+}
+
+fn submit(
+    console: &mut Console,
+    line: String,
+    commands: &ConsoleCommands,
+    ev_commands: &mut EventWriter<ConsoleCommand>,
+    ev_console: &mut EventWriter<ConsoleLine>,
+) {
+    let mut words = line.split_whitespace();
+    let name = match words.next() {
+        Some(name) => name.to_string(),
+        None => return,
+    };
+    console.print(format!("{}{}", CONSOLE_PROMPT, line.trim()));
+    if console.history.last() != Some(&line) {
+        console.history.push(line.clone());
+        if console.history.len() > CONSOLE_HISTORY_SIZE {
+            console.history.remove(0);
+        }
+    }
+    console.history_cursor = None;
+
+    if commands.get(&name).is_some() {
+        ev_commands.send(ConsoleCommand {
+            name,
+            args: words.map(String::from).collect(),
+        });
+    } else {
+        ev_console.send(ConsoleLine(format!("unknown command `{}`, try help", name)));
+    }
+}
+
+pub fn handle_console_commands(
+    mut console: ResMut<Console>,
+    mut ev_commands: EventReader<ConsoleCommand>,
+    commands: Res<ConsoleCommands>,
+) {
+    for command in ev_commands.iter() {
+        match command.name.as_str() {
+            "help" => {
+                let lines: Vec<String> = commands
+                    .iter()
+                    .map(|info| format!("{} {} - {}", info.name, info.args, info.help))
+                    .collect();
+                for line in lines {
+                    console.print(line);
+                }
+            }
+            "clear" => console.log.clear(),
+            _ => {}
+        }
+    }
+}
+
+pub fn print_console_lines(mut console: ResMut<Console>, mut ev_console: EventReader<ConsoleLine>) {
+    for ConsoleLine(line) in ev_console.iter() {
+        console.print(line.clone());
+    }
+}
+
+pub fn sync_console_overlay(
+    mut commands: Commands,
+    console: Res<Console>,
+    game_assets: Option<Res<GameAssets>>,
+    q_overlay: Query<Entity, With<ConsoleOverlay>>,
+    mut q_text: Query<&mut Text, With<ConsoleText>>,
+) {
+    if !console.is_changed() {
+        return;
+    }
+    if !console.open {
+        for e in q_overlay.iter() {
+            commands.entity(e).despawn();
+        }
+        return;
+    }
+
+    let mut lines: Vec<String> = console.log.iter().cloned().collect();
+    lines.push(format!("{}{}_", CONSOLE_PROMPT, console.input));
+    let content = lines.join("\n");
+    if let Ok(mut text) = q_text.get_single_mut() {
+        text.sections[0].value = content;
+    } else if let Some(game_assets) = game_assets {
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: CONSOLE_COLOR,
+                    custom_size: Some(Vec2::new(GAME_WIDTH, CONSOLE_HEIGHT)),
+                    ..Default::default()
+                },
+                transform: Transform::from_xyz(
+                    0.0,
+                    (GAME_HEIGHT - CONSOLE_HEIGHT) / 2.0,
+                    CONSOLE_Z,
+                ),
+                ..Default::default()
+            },
+            ConsoleOverlay,
+            Readable::screen_space(),
+        ));
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    content,
+                    TextStyle {
+                        font: game_assets.font.clone(),
+                        font_size: CONSOLE_FONT_SIZE,
+                        color: Color::WHITE,
+                    },
+                )
+                .with_alignment(TextAlignment::TOP_LEFT),
+                transform: Transform::from_translation(Vec3::new(
+                    -GAME_WIDTH / 2.0 + CONSOLE_PADDING,
+                    GAME_HEIGHT / 2.0 - CONSOLE_PADDING,
+                    CONSOLE_Z + 1.0,
+                )),
+                ..Default::default()
+            },
+            ConsoleOverlay,
+            ConsoleText,
+            Readable::screen_space(),
+        ));
+    }
+}
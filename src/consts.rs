@@ -1,16 +1,32 @@
-use bevy_rapier2d::prelude::Group;
-
 pub const GAME_WIDTH: f32 = 576.0;
 pub const GAME_HEIGHT: f32 = 324.0;
 
-pub const COLLISION_GROUP_GAME_OVER: Group = Group::GROUP_2;
-pub const COLLISION_GROUP_PLAYER: Group = Group::GROUP_3;
-pub const COLLISION_GROUP_OPENING: Group = Group::GROUP_4;
+pub const COLLISION_GROUP_GAME_OVER: u32 = 1 << 1;
+pub const COLLISION_GROUP_PLAYER: u32 = 1 << 2;
+pub const COLLISION_GROUP_OPENING: u32 = 1 << 3;
+pub const COLLISION_GROUP_PICKUP: u32 = 1 << 4;
+// apart from the other game over colliders, so pipes alone can be passed through
+pub const COLLISION_GROUP_PIPE: u32 = 1 << 5;
+pub const COLLISION_GROUP_HAZARD: u32 = 1 << 6;
+
+pub const HITBOX_FORGIVENESS: f32 = 1.0;
 
+pub const PIXELS_PER_METER: f32 = 36.0;
 pub const GRAVITY: f32 = -1080.0;
 pub const JUMP_IMPULSE_VALUE: f32 = 300.0;
+// frames of characters.png
+pub const PLAYER_SPRITE_SIZE: f32 = 24.0;
 
 pub const BASE_GAME_SPEED: f32 = 1.1;
 pub const BASE_MOVE_SPEED: f32 = 94.0;
 pub const ACCELERATION_FACTOR: f32 = 1.028;
-pub const SECONDS_BETWEEN_ACCELERATION_TICK: f32 = 3.6;
\ No newline at end of file
+pub const SECONDS_BETWEEN_ACCELERATION_TICK: f32 = 3.6;
+pub const PRACTICE_TIME_SCALE: f32 = 0.6;
+
+pub const REWIND_CHARGES: u8 = 3;
+pub const REWIND_BUFFER_SECONDS: f32 = 5.0;
+
+pub const MEDAL_BRONZE_SCORE: u16 = 10;
+pub const MEDAL_SILVER_SCORE: u16 = 20;
+pub const MEDAL_GOLD_SCORE: u16 = 30;
+pub const MEDAL_PLATINUM_SCORE: u16 = 40;
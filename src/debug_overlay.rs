@@ -0,0 +1,219 @@
+use bevy::{
+    diagnostic::{Diagnostics, FrameTimeDiagnosticsPlugin},
+    prelude::{
+        App, Color, Commands, Component, Entity, Input, KeyCode, Plugin, Query, Res, ResMut,
+        Resource, Transform, Vec2, Vec3, With, Without,
+    },
+    sprite::{Anchor, Sprite, SpriteBundle},
+    text::{Text, Text2dBundle, TextAlignment, TextStyle},
+};
+use iyes_loopless::{prelude::IntoConditionalSystem, state::CurrentState};
+
+use crate::{
+    app_states::AppState,
+    assets::GameAssets,
+    camera::Readable,
+    consts::{GAME_HEIGHT, GAME_WIDTH},
+    game::GameSpeed,
+    obstacles::{Obstacle, ObstaclesData},
+};
+
+const DEBUG_OVERLAY_TOGGLE_KEY: KeyCode = KeyCode::F3;
+
+const DEBUG_OVERLAY_Z: f32 = 940.0;
+const DEBUG_OVERLAY_PADDING: f32 = 6.0;
+const DEBUG_OVERLAY_FONT_SIZE: f32 = 8.0;
+const DEBUG_OVERLAY_TEXT_HEIGHT: f32 = 84.0;
+const DEBUG_OVERLAY_BACKGROUND_COLOR: Color = Color::rgba(0.1, 0.11, 0.15, 0.75);
+const DEBUG_OVERLAY_SIZE: Vec2 = Vec2::new(168.0, 124.0);
+
+// frame times history of the frame time diagnostic
+const FRAME_TIME_BARS: usize = 20;
+const FRAME_TIME_BAR_WIDTH: f32 = 6.0;
+const FRAME_TIME_BAR_PIXELS_PER_MS: f32 = 1.5;
+const FRAME_TIME_BUDGET_MS: f32 = 1000.0 / 60.0;
+const FRAME_TIME_BAR_COLOR: Color = Color::rgb(0.42, 0.84, 0.55);
+const FRAME_TIME_BAR_SLOW_COLOR: Color = Color::rgb(0.93, 0.36, 0.33);
+
+/// FPS, frame times, entity counts and game pace over the game, shown with `F3`.
+pub struct DebugOverlayPlugin;
+
+impl Plugin for DebugOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+            app.add_plugin(FrameTimeDiagnosticsPlugin);
+        }
+        app.init_resource::<DebugOverlay>()
+            .add_system(toggle_debug_overlay)
+            .add_system(update_debug_overlay.run_if(debug_overlay_visible));
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct DebugOverlay {
+    visible: bool,
+}
+
+#[derive(Component)]
+pub struct DebugOverlayElement;
+
+#[derive(Component)]
+pub struct DebugOverlayText;
+
+#[derive(Component)]
+pub struct FrameTimeBar(usize);
+
+pub fn debug_overlay_visible(overlay: Res<DebugOverlay>) -> bool {
+    overlay.visible
+}
+
+pub fn toggle_debug_overlay(
+    mut commands: Commands,
+    mut overlay: ResMut<DebugOverlay>,
+    keyboard_input: Res<Input<KeyCode>>,
+    game_assets: Option<Res<GameAssets>>,
+    q_elements: Query<Entity, With<DebugOverlayElement>>,
+) {
+    if !keyboard_input.just_pressed(DEBUG_OVERLAY_TOGGLE_KEY) {
+        return;
+    }
+    if overlay.visible {
+        for e in q_elements.iter() {
+            commands.entity(e).despawn();
+        }
+        overlay.visible = false;
+    } else if let Some(game_assets) = game_assets {
+        spawn_debug_overlay(&mut commands, &game_assets);
+        overlay.visible = true;
+    }
+}
+
+fn spawn_debug_overlay(commands: &mut Commands, game_assets: &GameAssets) {
+    let top_right = Vec2::new(
+        GAME_WIDTH / 2.0 - DEBUG_OVERLAY_PADDING,
+        GAME_HEIGHT / 2.0 - DEBUG_OVERLAY_PADDING,
+    );
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: DEBUG_OVERLAY_BACKGROUND_COLOR,
+                custom_size: Some(DEBUG_OVERLAY_SIZE + Vec2::splat(DEBUG_OVERLAY_PADDING)),
+                anchor: Anchor::TopRight,
+                ..Default::default()
+            },
+            transform: Transform::from_translation(
+                (top_right + Vec2::splat(DEBUG_OVERLAY_PADDING / 2.0)).extend(DEBUG_OVERLAY_Z),
+            ),
+            ..Default::default()
+        },
+        DebugOverlayElement,
+        Readable::screen_space(),
+    ));
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font: game_assets.font.clone(),
+                    font_size: DEBUG_OVERLAY_FONT_SIZE,
+                    color: Color::WHITE,
+                },
+            )
+            .with_alignment(TextAlignment::TOP_RIGHT),
+            transform: Transform::from_translation(top_right.extend(DEBUG_OVERLAY_Z + 1.0)),
+            ..Default::default()
+        },
+        DebugOverlayElement,
+        DebugOverlayText,
+        Readable::screen_space(),
+    ));
+    let graph_bottom_right = top_right - Vec2::new(0.0, DEBUG_OVERLAY_SIZE.y);
+    for i in 0..FRAME_TIME_BARS {
+        // the most recent frame on the right
+        let x = graph_bottom_right.x - (FRAME_TIME_BARS - 1 - i) as f32 * FRAME_TIME_BAR_WIDTH;
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: FRAME_TIME_BAR_COLOR,
+                    custom_size: Some(Vec2::new(FRAME_TIME_BAR_WIDTH - 1.0, 0.0)),
+                    anchor: Anchor::BottomRight,
+                    ..Default::default()
+                },
+                transform: Transform::from_translation(Vec3::new(
+                    x,
+                    graph_bottom_right.y,
+                    DEBUG_OVERLAY_Z + 1.0,
+                )),
+                ..Default::default()
+            },
+            DebugOverlayElement,
+            FrameTimeBar(i),
+            Readable::screen_space(),
+        ));
+    }
+}
+
+pub fn update_debug_overlay(
+    diagnostics: Res<Diagnostics>,
+    current_state: Option<Res<CurrentState<AppState>>>,
+    game_speed: Res<GameSpeed>,
+    obstacles_data: Res<ObstaclesData>,
+    q_obstacles: Query<&Obstacle>,
+    mut q_text: Query<&mut Text, With<DebugOverlayText>>,
+    mut q_bars: Query<(&mut Sprite, &FrameTimeBar), Without<DebugOverlayText>>,
+) {
+    let frame_time = diagnostics.get(FrameTimeDiagnosticsPlugin::FRAME_TIME);
+
+    if let Ok(mut text) = q_text.get_single_mut() {
+        let fps = diagnostics
+            .get(FrameTimeDiagnosticsPlugin::FPS)
+            .and_then(|fps| fps.smoothed())
+            .unwrap_or_default();
+        let frame_time_ms = frame_time
+            .and_then(|frame_time| frame_time.smoothed())
+            .unwrap_or_default();
+        let count = |variant: Obstacle| q_obstacles.iter().filter(|&&o| o == variant).count();
+        let state = current_state
+            .map(|state| format!("{:?}", state.0))
+            .unwrap_or_default();
+        text.sections[0].value = [
+            format!("{:.0} fps {:.1} ms", fps, frame_time_ms),
+            state,
+            format!("speed x{:.3}", game_speed.factor),
+            format!("traveled {:.0}px", obstacles_data.traveled_distance()),
+            format!(
+                "last obstacle {:.0}px",
+                obstacles_data.last_obstacle_distance()
+            ),
+            format!(
+                "pipes {} openings {} bounds {} hazards {}",
+                count(Obstacle::GameOver),
+                count(Obstacle::Opening),
+                count(Obstacle::GameOverStatic),
+                count(Obstacle::Hazard)
+            ),
+        ]
+        .join("\n");
+    }
+
+    let frame_times: Vec<f64> = frame_time
+        .map(|frame_time| frame_time.values().copied().collect())
+        .unwrap_or_default();
+    // history is oldest first, align the most recent frame on the last bar
+    let offset = FRAME_TIME_BARS as isize - frame_times.len() as isize;
+    for (mut sprite, bar) in q_bars.iter_mut() {
+        let frame_time_ms = usize::try_from(bar.0 as isize - offset)
+            .ok()
+            .and_then(|i| frame_times.get(i))
+            .copied()
+            .unwrap_or_default() as f32;
+        let height = (frame_time_ms * FRAME_TIME_BAR_PIXELS_PER_MS)
+            .min(DEBUG_OVERLAY_SIZE.y - DEBUG_OVERLAY_TEXT_HEIGHT);
+        sprite.custom_size = Some(Vec2::new(FRAME_TIME_BAR_WIDTH - 1.0, height));
+        sprite.color = if frame_time_ms > FRAME_TIME_BUDGET_MS {
+            FRAME_TIME_BAR_SLOW_COLOR
+        } else {
+            FRAME_TIME_BAR_COLOR
+        };
+    }
+}
@@ -1,65 +1,401 @@
-use bevy::prelude::{Commands, Entity, EventReader, EventWriter, Query, With};
-use bevy_rapier2d::prelude::CollisionEvent;
+use bevy::prelude::{
+    Commands, Component, Entity, EventReader, EventWriter, Query, Res, Transform, Vec2, With,
+};
 use iyes_loopless::state::NextState;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     app_states::{AppState, InGameState},
-    obstacles::Obstacle,
-    player::Player,
+    consts::BASE_MOVE_SPEED,
+    game::{GameSpeed, GameTick},
+    obstacles::{Obstacle, OpeningCrossing},
+    physics::ContactEvent,
+    player::{Invulnerable, Player, PlayerScore, Shielded},
+    stats::RunStats,
 };
 
-#[derive(Debug, Eq, PartialEq)]
+/// What ended a run, attached to every collider that kills the player.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeathCause {
+    TopBound,
+    Ground,
+    Pipe,
+    Bird,
+    Rock,
+}
+
+impl DeathCause {
+    pub fn label(&self) -> &'static str {
+        match self {
+            DeathCause::TopBound => "Flew too high",
+            DeathCause::Ground => "Hit the ground",
+            DeathCause::Pipe => "Hit a pipe",
+            DeathCause::Bird => "Hit a bird",
+            DeathCause::Rock => "Hit by a rock",
+        }
+    }
+}
+
+/// Gameplay events of a run. Every event carries the [`GameTick`] it happened on, so listeners
+/// do not need to query the world to make sense of it.
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum GameEvent {
-    PlayerHitGameOverCollider,
-    PlayerPassedAnOpening,
+    RunStarted {
+        tick: u64,
+    },
+    RunEnded {
+        tick: u64,
+        score: u16,
+        distance: f32,
+        cause: Option<DeathCause>,
+    },
+    PlayerFlapped {
+        player: Entity,
+        position: Vec2,
+        tick: u64,
+    },
+    PlayerHitGameOverCollider {
+        player: Entity,
+        collider: Entity,
+        obstacle: Obstacle,
+        cause: DeathCause,
+        /// Position of the player at the time of the hit.
+        position: Vec2,
+        tick: u64,
+        speed: f32,
+    },
+    PlayerPassedAnOpening {
+        player: Entity,
+        opening: Entity,
+        /// Center of the opening sensor.
+        opening_position: Vec2,
+        player_position: Vec2,
+        tick: u64,
+        speed: f32,
+    },
+    GameSpeedIncreased {
+        factor: f32,
+        speed: f32,
+        tick: u64,
+    },
+}
+
+impl GameEvent {
+    pub fn tick(&self) -> u64 {
+        match *self {
+            GameEvent::RunStarted { tick }
+            | GameEvent::RunEnded { tick, .. }
+            | GameEvent::PlayerFlapped { tick, .. }
+            | GameEvent::PlayerHitGameOverCollider { tick, .. }
+            | GameEvent::PlayerPassedAnOpening { tick, .. }
+            | GameEvent::GameSpeedIncreased { tick, .. } => tick,
+        }
+    }
 }
 
+/// Turns the contacts of the player into [`GameEvent`]s. Every contact ending the run is handled
+/// before the openings, so no point is awarded in the frame the player dies.
 pub fn dispatch_collision_events(
-    mut ev_collisions: EventReader<CollisionEvent>,
+    mut ev_contacts: EventReader<ContactEvent>,
     mut ev_game: EventWriter<GameEvent>,
-    q_player: Query<Entity, With<Player>>,
-    q_obstacles: Query<(Entity, &Obstacle)>,
+    q_player: Query<(Entity, &Transform, Option<&Invulnerable>, Option<&Shielded>), With<Player>>,
+    mut q_obstacles: Query<(
+        &Obstacle,
+        &Transform,
+        Option<&DeathCause>,
+        Option<&mut OpeningCrossing>,
+    )>,
+    game_speed: Res<GameSpeed>,
+    game_tick: Res<GameTick>,
+    run_stats: Res<RunStats>,
 ) {
-    for col_ev in ev_collisions.iter() {
-        match col_ev {
-            CollisionEvent::Started(ent_a, ent_b, _) => {
-                if q_player.contains(ent_a.clone()) && q_obstacles.contains(ent_b.clone()) {
-                    handle_player_obstacle_collision(
-                        q_obstacles.get(ent_b.clone()).unwrap(),
-                        &mut ev_game,
-                    );
-                } else if q_player.contains(ent_b.clone()) && q_obstacles.contains(ent_a.clone()) {
-                    handle_player_obstacle_collision(
-                        q_obstacles.get(ent_a.clone()).unwrap(),
-                        &mut ev_game,
-                    );
+    let speed = BASE_MOVE_SPEED * game_speed.factor;
+    // (player, collider, started)
+    let contacts: Vec<(Entity, Entity, bool)> = ev_contacts
+        .iter()
+        .filter_map(|contact| {
+            let (ent_a, ent_b, started) = match *contact {
+                ContactEvent::Started(ent_a, ent_b) => (ent_a, ent_b, true),
+                ContactEvent::Stopped(ent_a, ent_b) => (ent_a, ent_b, false),
+            };
+            if q_player.contains(ent_a) {
+                Some((ent_a, ent_b, started))
+            } else if q_player.contains(ent_b) {
+                Some((ent_b, ent_a, started))
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    let mut died = run_stats.death_cause.is_some();
+    for &(player, collider, started) in contacts.iter() {
+        if let (
+            true,
+            Ok((player, player_transform, None, None)),
+            Ok((&obstacle, _, death_cause, _)),
+        ) = (started, q_player.get(player), q_obstacles.get(collider))
+        {
+            match obstacle {
+                Obstacle::GameOverStatic | Obstacle::GameOver => {
+                    died = true;
+                    ev_game.send(GameEvent::PlayerHitGameOverCollider {
+                        player,
+                        collider,
+                        obstacle,
+                        cause: death_cause.copied().unwrap_or(DeathCause::Pipe),
+                        position: player_transform.translation.truncate(),
+                        tick: game_tick.0,
+                        speed,
+                    });
                 }
+                // the hazards send their own event
+                Obstacle::Hazard => died = true,
+                Obstacle::Opening => {}
             }
-            _ => {}
         }
     }
-}
 
-fn handle_player_obstacle_collision(
-    (_, obstacle): (Entity, &Obstacle),
-    ev_game: &mut EventWriter<GameEvent>,
-) {
-    match obstacle {
-        Obstacle::GameOverStatic | Obstacle::GameOver => {
-            ev_game.send(GameEvent::PlayerHitGameOverCollider)
+    for &(player, collider, started) in contacts.iter() {
+        if let (
+            Ok((player, player_transform, _, _)),
+            Ok((&Obstacle::Opening, obstacle_transform, _, Some(mut crossing))),
+        ) = (q_player.get(player), q_obstacles.get_mut(collider))
+        {
+            if started {
+                crossing.entered = true;
+                continue;
+            }
+            // the point is only awarded once the player fully crossed the sensor, leaving it on
+            // the far side, and never in the frame it died
+            let crossed = player_transform.translation.x >= obstacle_transform.translation.x;
+            if crossing.entered && !crossing.scored && crossed && !died {
+                crossing.scored = true;
+                ev_game.send(GameEvent::PlayerPassedAnOpening {
+                    player,
+                    opening: collider,
+                    opening_position: obstacle_transform.translation.truncate(),
+                    player_position: player_transform.translation.truncate(),
+                    tick: game_tick.0,
+                    speed,
+                });
+            }
         }
-        Obstacle::Opening => ev_game.send(GameEvent::PlayerPassedAnOpening),
     }
 }
 
+pub fn send_run_started_event(mut ev_game: EventWriter<GameEvent>, game_tick: Res<GameTick>) {
+    ev_game.send(GameEvent::RunStarted { tick: game_tick.0 });
+}
+
+pub fn send_run_ended_event(
+    mut ev_game: EventWriter<GameEvent>,
+    game_tick: Res<GameTick>,
+    player_score: Res<PlayerScore>,
+    run_stats: Res<RunStats>,
+) {
+    ev_game.send(GameEvent::RunEnded {
+        tick: game_tick.0,
+        score: player_score.value,
+        distance: run_stats.distance,
+        cause: run_stats.death_cause,
+    });
+}
+
+/// Ends the run in the frame the player died, this change of state has no screen transition to
+/// go through.
 pub fn handle_game_event_player_hit_game_over_collider(
     mut commands: Commands,
     mut ev_game: EventReader<GameEvent>,
 ) {
-    for _ in ev_game
+    if ev_game
         .iter()
-        .filter(|&ev| ev == &GameEvent::PlayerHitGameOverCollider)
+        .any(|ev| matches!(ev, GameEvent::PlayerHitGameOverCollider { .. }))
     {
-        commands.insert_resource(NextState(AppState::InGame(InGameState::ReadyToStart)));
+        commands.insert_resource(NextState(AppState::InGame(InGameState::GameOver)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        ecs::event::Events,
+        prelude::{App, ResMut, Resource},
+    };
+    use iyes_loopless::{
+        prelude::{AppLooplessStateExt, IntoConditionalSystem},
+        state::CurrentState,
+    };
+
+    use super::*;
+    use crate::{
+        player::handle_game_event_player_passed_opening,
+        schedule::{GameSchedulePlugin, GameStage},
+        stats::track_death_cause,
+    };
+
+    /// Frames the run went through while playing.
+    #[derive(Resource, Default)]
+    struct PlayingFrames(u32);
+
+    fn count_playing_frames(mut frames: ResMut<PlayingFrames>) {
+        frames.0 += 1;
+    }
+
+    /// A player by an opening and a pipe, with the systems turning their contacts into a score
+    /// and ending the run, in the stages they run in.
+    struct Run {
+        app: App,
+        player: Entity,
+        opening: Entity,
+        pipe: Entity,
+    }
+
+    impl Run {
+        fn new() -> Self {
+            let playing = AppState::InGame(InGameState::Playing);
+            let mut app = App::new();
+            app.add_plugin(GameSchedulePlugin)
+                .add_loopless_state(playing.clone())
+                .add_event::<ContactEvent>()
+                .add_event::<GameEvent>()
+                .insert_resource(GameSpeed { factor: 1.0 })
+                .init_resource::<GameTick>()
+                .init_resource::<PlayerScore>()
+                .init_resource::<RunStats>()
+                .init_resource::<PlayingFrames>()
+                .add_system_to_stage(GameStage::Input, count_playing_frames.run_in_state(playing))
+                .add_system_to_stage(GameStage::CollisionDispatch, dispatch_collision_events)
+                .add_system_to_stage(GameStage::Scoring, handle_game_event_player_passed_opening)
+                .add_system_to_stage(GameStage::Scoring, track_death_cause)
+                .add_system_to_stage(
+                    GameStage::Scoring,
+                    handle_game_event_player_hit_game_over_collider,
+                );
+            let player = app.world.spawn((Player, Transform::default())).id();
+            let opening = app
+                .world
+                .spawn((
+                    Obstacle::Opening,
+                    OpeningCrossing::default(),
+                    Transform::default(),
+                ))
+                .id();
+            let pipe = app
+                .world
+                .spawn((Obstacle::GameOver, DeathCause::Pipe, Transform::default()))
+                .id();
+            Self {
+                app,
+                player,
+                opening,
+                pipe,
+            }
+        }
+
+        /// Plays a frame reporting these contacts, the player being `player_x` from the center
+        /// of the opening.
+        fn frame(&mut self, player_x: f32, contacts: &[ContactEvent]) {
+            if let Some(mut t) = self.app.world.get_mut::<Transform>(self.player) {
+                t.translation.x = player_x;
+            }
+            let mut events = self.app.world.resource_mut::<Events<ContactEvent>>();
+            for contact in contacts {
+                events.send(*contact);
+            }
+            drop(events);
+            self.app.update();
+        }
+
+        fn score(&self) -> u16 {
+            self.app.world.resource::<PlayerScore>().value
+        }
+
+        fn death_cause(&self) -> Option<DeathCause> {
+            self.app.world.resource::<RunStats>().death_cause
+        }
+
+        fn state(&self) -> AppState {
+            self.app
+                .world
+                .resource::<CurrentState<AppState>>()
+                .0
+                .clone()
+        }
+
+        fn playing_frames(&self) -> u32 {
+            self.app.world.resource::<PlayingFrames>().0
+        }
+    }
+
+    #[test]
+    fn crossing_an_opening_scores() {
+        let mut run = Run::new();
+        run.frame(-12.0, &[ContactEvent::Started(run.player, run.opening)]);
+        assert_eq!(run.score(), 0);
+        // contacts may list the player second
+        run.frame(12.0, &[ContactEvent::Stopped(run.opening, run.player)]);
+        assert_eq!(run.score(), 1);
+        assert_eq!(run.death_cause(), None);
+    }
+
+    #[test]
+    fn grazing_an_opening_does_not_score() {
+        let mut run = Run::new();
+        run.frame(-12.0, &[ContactEvent::Started(run.player, run.opening)]);
+        run.frame(-4.0, &[ContactEvent::Stopped(run.player, run.opening)]);
+        assert_eq!(run.score(), 0);
+    }
+
+    #[test]
+    fn leaving_an_opening_never_entered_does_not_score() {
+        let mut run = Run::new();
+        run.frame(12.0, &[ContactEvent::Stopped(run.player, run.opening)]);
+        assert_eq!(run.score(), 0);
+    }
+
+    #[test]
+    fn re_entering_an_opening_does_not_score_twice() {
+        let mut run = Run::new();
+        run.frame(-12.0, &[ContactEvent::Started(run.player, run.opening)]);
+        run.frame(12.0, &[ContactEvent::Stopped(run.player, run.opening)]);
+        run.frame(8.0, &[ContactEvent::Started(run.player, run.opening)]);
+        run.frame(12.0, &[ContactEvent::Stopped(run.player, run.opening)]);
+        assert_eq!(run.score(), 1);
+    }
+
+    #[test]
+    fn dying_inside_an_opening_does_not_score() {
+        let mut run = Run::new();
+        run.frame(-12.0, &[ContactEvent::Started(run.player, run.opening)]);
+        run.frame(0.0, &[ContactEvent::Started(run.player, run.pipe)]);
+        run.frame(12.0, &[ContactEvent::Stopped(run.player, run.opening)]);
+        assert_eq!(run.score(), 0);
+        assert_eq!(run.death_cause(), Some(DeathCause::Pipe));
+    }
+
+    #[test]
+    fn score_and_death_in_the_same_frame_resolve_deterministically() {
+        // whichever order the physics reports the contacts of the frame in
+        for opening_first in [true, false] {
+            let mut run = Run::new();
+            run.frame(-12.0, &[ContactEvent::Started(run.player, run.opening)]);
+            let leave = ContactEvent::Stopped(run.player, run.opening);
+            let hit = ContactEvent::Started(run.pipe, run.player);
+            let contacts = if opening_first {
+                [leave, hit]
+            } else {
+                [hit, leave]
+            };
+            run.frame(12.0, &contacts);
+            let frames = run.playing_frames();
+            assert_eq!(run.score(), 0);
+            // the next frame is already a game over one, nothing plays after the death
+            run.frame(12.0, &[]);
+            assert_eq!(run.state(), AppState::InGame(InGameState::GameOver));
+            assert_eq!(run.playing_frames(), frames);
+            assert_eq!(run.score(), 0);
+            assert_eq!(run.death_cause(), Some(DeathCause::Pipe));
+        }
     }
 }
@@ -1,18 +1,123 @@
+use std::{collections::BTreeMap, time::Duration};
+
 use bevy::{
-    prelude::{Component, Local, Query, Res, ResMut, Resource, Transform, With},
+    prelude::{
+        Commands, Component, EventReader, EventWriter, Input, KeyCode, Local, Query, Res, ResMut,
+        Resource, Transform, With,
+    },
     time::{Time, Timer, TimerMode},
 };
 
 use crate::{
-    consts::{ACCELERATION_FACTOR, BASE_MOVE_SPEED, GAME_WIDTH, SECONDS_BETWEEN_ACCELERATION_TICK},
+    assets::GameAssets,
+    console::{ConsoleCommand, ConsoleCommandInfo, ConsoleLine},
+    consts::{
+        ACCELERATION_FACTOR, BASE_GAME_SPEED, BASE_MOVE_SPEED, GAME_WIDTH, PRACTICE_TIME_SCALE,
+        SECONDS_BETWEEN_ACCELERATION_TICK,
+    },
+    events::GameEvent,
+    ui::{free_toast_slot, spawn_toast, Toast},
     world::Ground,
 };
 
+const FRAME_ADVANCE_TOGGLE_KEY: KeyCode = KeyCode::F5;
+const FRAME_ADVANCE_STEP_KEY: KeyCode = KeyCode::F6;
+const FRAME_ADVANCE_STEP: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+const PRACTICE_TOGGLE_KEY: KeyCode = KeyCode::P;
+
+const TIME_SCALE_CONSOLE: &str = "console";
+const TIME_SCALE_PRACTICE: &str = "practice";
+
+pub const GAME_CONSOLE_COMMANDS: [ConsoleCommandInfo; 2] = [
+    ConsoleCommandInfo {
+        name: "speed",
+        args: "<factor>",
+        help: "set the game speed factor",
+        completions: &[],
+    },
+    ConsoleCommandInfo {
+        name: "timescale",
+        args: "<scale>",
+        help: "set the gameplay time scale",
+        completions: &[],
+    },
+];
+
 #[derive(Resource)]
 pub struct GameSpeed {
     pub factor: f32,
 }
 
+/// Speed of the gameplay clock relative to the real one, as the product of the factors set by
+/// each source: practice mode, power-ups or the console.
+#[derive(Resource, Default, Debug, Clone)]
+pub struct TimeScale {
+    factors: BTreeMap<&'static str, f32>,
+    frame_advance: bool,
+    step_requested: bool,
+}
+
+impl TimeScale {
+    pub fn scale(&self) -> f32 {
+        self.factors.values().product()
+    }
+
+    pub fn set_factor(&mut self, source: &'static str, factor: f32) {
+        self.factors.insert(source, factor);
+    }
+
+    pub fn remove_factor(&mut self, source: &'static str) {
+        self.factors.remove(source);
+    }
+
+    /// While frame advance is on, the gameplay clock only moves one frame per requested step.
+    pub fn frame_advance(&self) -> bool {
+        self.frame_advance
+    }
+
+    pub fn set_frame_advance(&mut self, frame_advance: bool) {
+        self.frame_advance = frame_advance;
+        self.step_requested = false;
+    }
+
+    pub fn request_step(&mut self) {
+        self.step_requested = true;
+    }
+}
+
+/// The gameplay clock, read by everything simulating the world in place of [`Time`]: the real
+/// frame time scaled by the [`TimeScale`]. Menus, transitions and tweens keep the real time.
+#[derive(Resource, Default, Debug, Clone, Copy)]
+pub struct GameTime {
+    delta: Duration,
+    elapsed: Duration,
+}
+
+impl GameTime {
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    pub fn delta_seconds(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+
+    pub fn elapsed_seconds(&self) -> f32 {
+        self.elapsed.as_secs_f32()
+    }
+}
+
+/// Slows the whole game down to learn it, runs played this way are not recorded.
+#[derive(Resource, Default, Debug, Clone, Copy)]
+pub struct PracticeMode {
+    pub enabled: bool,
+}
+
+/// Number of frames played since the run started.
+#[derive(Resource, Default, Debug, Clone, Copy)]
+pub struct GameTick(pub u64);
+
 #[derive(Component)]
 pub struct HorizontalMove {
     pub factor: f32,
@@ -36,24 +141,107 @@ impl Default for UpdateGameSpeedData {
 pub fn move_game_elements_horizontal(
     mut q_elements: Query<(&HorizontalMove, &mut Transform)>,
     game_speed: Res<GameSpeed>,
-    time: Res<Time>,
+    game_time: Res<GameTime>,
 ) {
     for (m, mut t) in q_elements.iter_mut() {
-        t.translation.x -= BASE_MOVE_SPEED * m.factor * game_speed.factor * time.delta_seconds();
+        t.translation.x -=
+            BASE_MOVE_SPEED * m.factor * game_speed.factor * game_time.delta_seconds();
     }
 }
 
 pub fn update_game_speed(
     mut data: Local<UpdateGameSpeedData>,
     mut game_speed: ResMut<GameSpeed>,
-    time: Res<Time>,
+    mut ev_game: EventWriter<GameEvent>,
+    game_tick: Res<GameTick>,
+    game_time: Res<GameTime>,
 ) {
-    data.acceleration_tick_timer.tick(time.delta());
+    data.acceleration_tick_timer.tick(game_time.delta());
     if data.acceleration_tick_timer.just_finished() {
         game_speed.factor *= ACCELERATION_FACTOR;
+        ev_game.send(GameEvent::GameSpeedIncreased {
+            factor: game_speed.factor,
+            speed: BASE_MOVE_SPEED * game_speed.factor,
+            tick: game_tick.0,
+        });
+    }
+}
+
+pub fn advance_game_tick(mut game_tick: ResMut<GameTick>, game_time: Res<GameTime>) {
+    // frames frozen by frame advance are not played
+    if !game_time.delta.is_zero() {
+        game_tick.0 += 1;
+    }
+}
+
+pub fn update_game_time(
+    mut game_time: ResMut<GameTime>,
+    mut time_scale: ResMut<TimeScale>,
+    time: Res<Time>,
+) {
+    game_time.delta = if !time_scale.frame_advance {
+        time.delta().mul_f32(time_scale.scale())
+    } else if std::mem::take(&mut time_scale.step_requested) {
+        FRAME_ADVANCE_STEP
+    } else {
+        Duration::ZERO
+    };
+    game_time.elapsed += game_time.delta;
+}
+
+pub fn frame_advance_on_input(
+    mut time_scale: ResMut<TimeScale>,
+    keyboard_input: Res<Input<KeyCode>>,
+) {
+    if keyboard_input.just_pressed(FRAME_ADVANCE_TOGGLE_KEY) {
+        let frame_advance = !time_scale.frame_advance;
+        time_scale.set_frame_advance(frame_advance);
+    }
+    if time_scale.frame_advance && keyboard_input.just_pressed(FRAME_ADVANCE_STEP_KEY) {
+        time_scale.request_step();
     }
 }
 
+pub fn toggle_practice_mode_on_input(
+    mut commands: Commands,
+    mut practice_mode: ResMut<PracticeMode>,
+    mut time_scale: ResMut<TimeScale>,
+    keyboard_input: Res<Input<KeyCode>>,
+    game_assets: Res<GameAssets>,
+    q_toasts: Query<&Toast>,
+) {
+    if !keyboard_input.just_pressed(PRACTICE_TOGGLE_KEY) {
+        return;
+    }
+    practice_mode.enabled = !practice_mode.enabled;
+    let message = if practice_mode.enabled {
+        time_scale.set_factor(TIME_SCALE_PRACTICE, PRACTICE_TIME_SCALE);
+        "Slowed down, runs are not recorded"
+    } else {
+        time_scale.remove_factor(TIME_SCALE_PRACTICE);
+        "Back to full speed"
+    };
+    spawn_toast(
+        &mut commands,
+        game_assets.font.clone(),
+        "Practice mode",
+        message,
+        free_toast_slot(&q_toasts),
+    );
+}
+
+pub fn not_practicing(practice_mode: Res<PracticeMode>) -> bool {
+    !practice_mode.enabled
+}
+
+pub fn reset_game_tick(mut game_tick: ResMut<GameTick>) {
+    *game_tick = GameTick::default();
+}
+
+pub fn reset_game_speed(mut game_speed: ResMut<GameSpeed>) {
+    game_speed.factor = BASE_GAME_SPEED;
+}
+
 pub fn ground_buffer_swap(mut q_ground_elements: Query<&mut Transform, With<Ground>>) {
     for mut t in q_ground_elements.iter_mut() {
         if t.translation.x < -GAME_WIDTH / 2. {
@@ -61,3 +249,33 @@ pub fn ground_buffer_swap(mut q_ground_elements: Query<&mut Transform, With<Grou
         }
     }
 }
+
+pub fn handle_game_console_commands(
+    mut ev_commands: EventReader<ConsoleCommand>,
+    mut ev_console: EventWriter<ConsoleLine>,
+    mut game_speed: ResMut<GameSpeed>,
+    mut time_scale: ResMut<TimeScale>,
+) {
+    for command in ev_commands.iter() {
+        let result = match command.name.as_str() {
+            "speed" => command.arg::<f32>(0).and_then(|factor| {
+                if !factor.is_finite() || factor < 0.0 {
+                    return Err(
+                        "the game speed factor cannot be negative, infinite or NaN".to_string()
+                    );
+                }
+                game_speed.factor = factor;
+                Ok(format!("game speed factor set to {}", factor))
+            }),
+            "timescale" => command.arg::<f32>(0).and_then(|scale| {
+                if !scale.is_finite() || scale < 0.0 {
+                    return Err("the time scale cannot be negative, infinite or NaN".to_string());
+                }
+                time_scale.set_factor(TIME_SCALE_CONSOLE, scale);
+                Ok(format!("time scale set to {}", time_scale.scale()))
+            }),
+            _ => continue,
+        };
+        ev_console.send(result.into());
+    }
+}
@@ -0,0 +1,303 @@
+use bevy::{
+    prelude::{
+        App, Color, Commands, Component, Entity, EventReader, EventWriter, Plugin, Query, Res,
+        ResMut, Resource, Transform, Vec2, With,
+    },
+    sprite::{Sprite, SpriteBundle, SpriteSheetBundle, TextureAtlasSprite},
+};
+use bevy_turborand::{DelegatedRng, GlobalRng};
+use iyes_loopless::prelude::ConditionSet;
+use serde::Deserialize;
+
+use crate::{
+    animations::{AnimationClips, Animator, CLIP_IDLE},
+    app_states::{AppState, InGameState},
+    assets::GameAssets,
+    consts::{
+        BASE_MOVE_SPEED, COLLISION_GROUP_HAZARD, COLLISION_GROUP_PLAYER, GAME_HEIGHT, GAME_WIDTH,
+    },
+    events::{DeathCause, GameEvent},
+    game::{GameSpeed, GameTick, GameTime, HorizontalMove},
+    obstacles::{
+        leaves_passable_corridor, opening_band, Obstacle, ObstacleSpawned, ObstaclesData,
+        OpeningCrossing,
+    },
+    physics::{ContactEvent, Hitbox, PhysicsBody},
+    player::{Invulnerable, Player, Shielded},
+    rewind::DroppedOnRewind,
+    schedule::GameStage,
+    transitions::no_screen_transition,
+};
+
+const HAZARDS_DATA: &str = include_str!("../assets/sprites/hazards.ron");
+
+// the first pipes of a run are left alone
+const HAZARD_GRACE_OBSTACLES: u32 = 4;
+const HAZARD_SPAWN_CHANCE: f32 = 0.3;
+const HAZARD_PLACEMENT_ATTEMPTS: usize = 4;
+const HAZARD_Z: f32 = 3.0;
+const HAZARD_DESPAWN_MARGIN: f32 = 32.0;
+
+// birds fly faster than the world scrolls
+const BIRD_MOVE_FACTOR: f32 = 1.8;
+const BIRD_AMPLITUDE: (f32, f32) = (10.0, 28.0);
+const BIRD_FREQUENCY: f32 = 3.0;
+
+// rocks hang at the top of the screen until they get this close to the player
+const ROCK_DROP_X: f32 = GAME_WIDTH / 5.0;
+const ROCK_GRAVITY: f32 = 520.0;
+const ROCK_SPIN: f32 = 6.0;
+
+/// Enemy birds and falling rocks. A hazard may come between two pipes once the first few pipes
+/// are passed, always leaving room for an opening above or below it.
+pub struct HazardsPlugin;
+
+impl Plugin for HazardsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HazardDefinitions>()
+            .add_system_set_to_stage(
+                GameStage::Movement,
+                ConditionSet::new()
+                    .run_in_state(AppState::InGame(InGameState::Playing))
+                    .run_if(no_screen_transition)
+                    .with_system(fly_enemy_birds)
+                    .with_system(drop_rocks)
+                    .with_system(despawn_passed_hazards)
+                    .into(),
+            )
+            .add_system_set_to_stage(
+                GameStage::CollisionDispatch,
+                ConditionSet::new()
+                    .run_in_state(AppState::InGame(InGameState::Playing))
+                    .run_if(no_screen_transition)
+                    .with_system(schedule_hazards)
+                    .with_system(handle_hazard_collisions)
+                    .into(),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BirdDefinition {
+    /// Animations of the bird in `characters.png`.
+    pub clips: AnimationClips,
+    pub collider_radius: f32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RockDefinition {
+    pub size: f32,
+    pub color: String,
+}
+
+/// The hazards, defined in `sprites/hazards.ron`.
+#[derive(Resource, Debug, Clone, Deserialize)]
+pub struct HazardDefinitions {
+    pub bird: BirdDefinition,
+    pub rock: RockDefinition,
+}
+
+impl Default for HazardDefinitions {
+    fn default() -> Self {
+        ron::from_str(HAZARDS_DATA).expect("the hazards data file is invalid")
+    }
+}
+
+/// Flies right to left along a sine wave around `base_y`.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct EnemyBird {
+    base_y: f32,
+    amplitude: f32,
+    phase: f32,
+}
+
+/// Hangs at the top of the screen, then falls once close enough to the player.
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct FallingRock {
+    speed: Option<f32>,
+}
+
+/// Rolls for a hazard each time an obstacle is spawned, placing it halfway between the two newest
+/// openings.
+pub fn schedule_hazards(
+    mut commands: Commands,
+    mut ev_obstacles: EventReader<ObstacleSpawned>,
+    mut global_rng: ResMut<GlobalRng>,
+    obstacles_data: Res<ObstaclesData>,
+    definitions: Res<HazardDefinitions>,
+    game_assets: Res<GameAssets>,
+    q_openings: Query<&Transform, With<OpeningCrossing>>,
+) {
+    let obstacle_spawned = ev_obstacles.iter().count() > 0;
+    if !obstacle_spawned
+        || obstacles_data.spawned_obstacles() <= HAZARD_GRACE_OBSTACLES
+        || global_rng.f32() >= HAZARD_SPAWN_CHANCE
+    {
+        return;
+    }
+
+    let mut openings_x: Vec<f32> = q_openings.iter().map(|t| t.translation.x).collect();
+    openings_x.sort_by(|a, b| b.total_cmp(a));
+    let gap_center_x = match openings_x[..] {
+        [newest, previous, ..] => (newest + previous) / 2.0,
+        _ => return,
+    };
+
+    if global_rng.f32() < 0.5 {
+        spawn_rock(&mut commands, &definitions.rock, gap_center_x);
+        return;
+    }
+    let (bottom, top) = opening_band();
+    let radius = definitions.bird.collider_radius;
+    for _ in 0..HAZARD_PLACEMENT_ATTEMPTS {
+        let amplitude = BIRD_AMPLITUDE.0 + global_rng.f32() * (BIRD_AMPLITUDE.1 - BIRD_AMPLITUDE.0);
+        let base_y = bottom + global_rng.f32() * (top - bottom);
+        if leaves_passable_corridor(base_y - amplitude - radius, base_y + amplitude + radius) {
+            let bird = EnemyBird {
+                base_y,
+                amplitude,
+                phase: global_rng.f32() * std::f32::consts::TAU,
+            };
+            spawn_bird(&mut commands, &game_assets, &definitions.bird, bird);
+            return;
+        }
+    }
+}
+
+fn spawn_bird(
+    commands: &mut Commands,
+    game_assets: &GameAssets,
+    definition: &BirdDefinition,
+    bird: EnemyBird,
+) {
+    let animator = Animator::new(definition.clips.clone(), CLIP_IDLE);
+    let radius = definition.collider_radius;
+    commands.spawn((
+        SpriteSheetBundle {
+            texture_atlas: game_assets.characters_atlas.clone(),
+            sprite: TextureAtlasSprite {
+                index: animator.atlas_index().unwrap_or(0),
+                flip_x: true,
+                ..Default::default()
+            },
+            transform: Transform::from_xyz(
+                GAME_WIDTH / 2.0 + radius * 2.0,
+                bird.base_y + bird.amplitude * bird.phase.sin(),
+                HAZARD_Z,
+            ),
+            ..Default::default()
+        },
+        animator,
+        bird,
+        HorizontalMove {
+            factor: BIRD_MOVE_FACTOR,
+        },
+        PhysicsBody::kinematic(Hitbox::Circle { radius })
+            .sensor()
+            .with_groups(COLLISION_GROUP_HAZARD, COLLISION_GROUP_PLAYER),
+        Obstacle::Hazard,
+        DeathCause::Bird,
+        DroppedOnRewind,
+    ));
+}
+
+fn spawn_rock(commands: &mut Commands, definition: &RockDefinition, x: f32) {
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::hex(&definition.color).expect("the rock color is not a valid hex"),
+                custom_size: Some(Vec2::splat(definition.size)),
+                ..Default::default()
+            },
+            // its lower half shows at the top of the screen
+            transform: Transform::from_xyz(x, GAME_HEIGHT / 2.0, HAZARD_Z),
+            ..Default::default()
+        },
+        FallingRock::default(),
+        HorizontalMove { factor: 1.0 },
+        PhysicsBody::kinematic(Hitbox::Rect {
+            half_extents: Vec2::splat(definition.size / 2.0),
+        })
+        .sensor()
+        .with_groups(COLLISION_GROUP_HAZARD, COLLISION_GROUP_PLAYER),
+        Obstacle::Hazard,
+        DeathCause::Rock,
+        DroppedOnRewind,
+    ));
+}
+
+pub fn fly_enemy_birds(
+    mut q_birds: Query<(&mut EnemyBird, &mut Transform)>,
+    game_time: Res<GameTime>,
+) {
+    for (mut bird, mut t) in q_birds.iter_mut() {
+        bird.phase += BIRD_FREQUENCY * game_time.delta_seconds();
+        t.translation.y = bird.base_y + bird.amplitude * bird.phase.sin();
+    }
+}
+
+pub fn drop_rocks(
+    mut q_rocks: Query<(&mut FallingRock, &mut Transform)>,
+    game_time: Res<GameTime>,
+) {
+    let dt = game_time.delta_seconds();
+    for (mut rock, mut t) in q_rocks.iter_mut() {
+        if rock.speed.is_none() && t.translation.x < ROCK_DROP_X {
+            rock.speed = Some(0.0);
+        }
+        if let Some(speed) = rock.speed.as_mut() {
+            *speed += ROCK_GRAVITY * dt;
+            t.translation.y -= *speed * dt;
+            t.rotate_z(-ROCK_SPIN * dt);
+        }
+    }
+}
+
+pub fn despawn_passed_hazards(
+    mut commands: Commands,
+    q_hazards: Query<(Entity, &Transform, &Obstacle)>,
+) {
+    for (e, t, &o) in q_hazards.iter() {
+        let off_screen = t.translation.x < -GAME_WIDTH / 2.0 - HAZARD_DESPAWN_MARGIN
+            || t.translation.y < -GAME_HEIGHT / 2.0 - HAZARD_DESPAWN_MARGIN;
+        if o == Obstacle::Hazard && off_screen {
+            commands.entity(e).despawn();
+        }
+    }
+}
+
+/// Hazards end the run on contact, unless the player is [`Invulnerable`] or [`Shielded`].
+pub fn handle_hazard_collisions(
+    mut ev_contacts: EventReader<ContactEvent>,
+    mut ev_game: EventWriter<GameEvent>,
+    q_player: Query<(Entity, &Transform, Option<&Invulnerable>, Option<&Shielded>), With<Player>>,
+    q_hazards: Query<(&Obstacle, &DeathCause)>,
+    game_speed: Res<GameSpeed>,
+    game_tick: Res<GameTick>,
+) {
+    let speed = BASE_MOVE_SPEED * game_speed.factor;
+    for contact in ev_contacts.iter() {
+        let (ent_a, ent_b) = match *contact {
+            ContactEvent::Started(ent_a, ent_b) => (ent_a, ent_b),
+            ContactEvent::Stopped(..) => continue,
+        };
+        let (player, collider) = if q_player.contains(ent_a) {
+            (ent_a, ent_b)
+        } else {
+            (ent_b, ent_a)
+        };
+        if let (Ok((player, player_transform, None, None)), Ok((&Obstacle::Hazard, &cause))) =
+            (q_player.get(player), q_hazards.get(collider))
+        {
+            ev_game.send(GameEvent::PlayerHitGameOverCollider {
+                player,
+                collider,
+                obstacle: Obstacle::Hazard,
+                cause,
+                position: player_transform.translation.truncate(),
+                tick: game_tick.0,
+                speed,
+            });
+        }
+    }
+}
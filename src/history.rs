@@ -0,0 +1,110 @@
+use bevy::prelude::{Commands, Res, ResMut, Resource};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    characters::{CharacterRoster, SelectedCharacter},
+    medals::{Medal, MedalThresholds},
+    mutators::Mutators,
+    persistence::{self, Profile},
+    player::PlayerScore,
+    stats::RunStats,
+};
+
+const HISTORY_KEY: &str = "history";
+const HISTORY_MAX_RUNS: usize = 100;
+const LEADERBOARD_SIZE: usize = 5;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub score: u16,
+    pub medal: Option<Medal>,
+    #[serde(default)]
+    pub stats: RunStats,
+    /// Id of the character flown, missing from the runs recorded before the roster.
+    #[serde(default)]
+    pub character: Option<String>,
+    /// Category of the run, the runs recorded before the mutators being standard ones.
+    #[serde(default)]
+    pub mutators: Mutators,
+}
+
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RunHistory {
+    pub runs: Vec<RunRecord>,
+}
+
+impl RunHistory {
+    pub fn last_run(&self) -> Option<&RunRecord> {
+        self.runs.last()
+    }
+
+    /// Best score among every recorded run but the last one, within the category of the last one.
+    pub fn previous_best_score(&self) -> u16 {
+        let (last, previous_runs) = match self.runs.split_last() {
+            Some(split) => split,
+            None => return 0,
+        };
+        previous_runs
+            .iter()
+            .filter(|r| r.mutators == last.mutators)
+            .map(|r| r.score)
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn best_score(&self, mutators: &Mutators) -> u16 {
+        self.runs
+            .iter()
+            .filter(|r| r.mutators == *mutators)
+            .map(|r| r.score)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The best recorded runs of a category, highest score first.
+    pub fn leaderboard(&self, mutators: &Mutators) -> Vec<&RunRecord> {
+        let mut runs: Vec<&RunRecord> = self
+            .runs
+            .iter()
+            .filter(|r| r.mutators == *mutators)
+            .collect();
+        runs.sort_by(|a, b| b.score.cmp(&a.score));
+        runs.truncate(LEADERBOARD_SIZE);
+        runs
+    }
+
+    fn push(&mut self, record: RunRecord) {
+        self.runs.push(record);
+        if self.runs.len() > HISTORY_MAX_RUNS {
+            let overflow = self.runs.len() - HISTORY_MAX_RUNS;
+            self.runs.drain(..overflow);
+        }
+    }
+}
+
+pub fn load_run_history(mut commands: Commands, profile: Res<Profile>) {
+    commands.insert_resource(persistence::load_or_default::<RunHistory>(
+        &profile,
+        HISTORY_KEY,
+    ));
+}
+
+pub fn record_finished_run(
+    mut history: ResMut<RunHistory>,
+    player_score: Res<PlayerScore>,
+    run_stats: Res<RunStats>,
+    medal_thresholds: Res<MedalThresholds>,
+    roster: Res<CharacterRoster>,
+    selected_character: Res<SelectedCharacter>,
+    mutators: Res<Mutators>,
+    profile: Res<Profile>,
+) {
+    history.push(RunRecord {
+        score: player_score.value,
+        medal: medal_thresholds.medal_for(player_score.value),
+        stats: run_stats.clone(),
+        character: Some(roster.get_or_first(&selected_character.id).id.clone()),
+        mutators: mutators.clone(),
+    });
+    persistence::save(&profile, HISTORY_KEY, &*history);
+}
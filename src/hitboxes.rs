@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+
+use bevy::prelude::{Resource, Vec2};
+use serde::Deserialize;
+
+use crate::{consts::HITBOX_FORGIVENESS, physics::Hitbox};
+
+const HITBOXES_DATA: &str = include_str!("../assets/sprites/hitboxes.ron");
+
+#[derive(Deserialize)]
+enum HitboxShape {
+    Circle(f32),
+    Rect(f32, f32),
+    Polygon(Vec<(f32, f32)>),
+    Compound(Vec<((f32, f32), HitboxShape)>),
+}
+
+impl From<HitboxShape> for Hitbox {
+    fn from(shape: HitboxShape) -> Self {
+        match shape {
+            HitboxShape::Circle(radius) => Hitbox::Circle { radius },
+            HitboxShape::Rect(half_width, half_height) => Hitbox::Rect {
+                half_extents: Vec2::new(half_width, half_height),
+            },
+            HitboxShape::Polygon(points) => Hitbox::Polygon {
+                points: points.into_iter().map(Vec2::from).collect(),
+            },
+            HitboxShape::Compound(parts) => Hitbox::Compound {
+                parts: parts
+                    .into_iter()
+                    .map(|(offset, part)| (Vec2::from(offset), part.into()))
+                    .collect(),
+            },
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct HitboxesData {
+    characters: HashMap<String, HitboxShape>,
+    pipe: HitboxShape,
+}
+
+/// Collider shapes matching the sprites, defined next to them in `sprites/hitboxes.ron`.
+#[derive(Resource, Debug, Clone)]
+pub struct HitboxDefinitions {
+    characters: HashMap<String, Hitbox>,
+    /// The pipe standing up, with its opening end on top.
+    pub pipe: Hitbox,
+}
+
+impl Default for HitboxDefinitions {
+    fn default() -> Self {
+        let data: HitboxesData =
+            ron::from_str(HITBOXES_DATA).expect("the hitboxes data file is invalid");
+        Self {
+            characters: data
+                .characters
+                .into_iter()
+                .map(|(name, shape)| (name, shape.into()))
+                .collect(),
+            pipe: data.pipe.into(),
+        }
+    }
+}
+
+impl HitboxDefinitions {
+    pub fn character(&self, name: &str) -> Option<&Hitbox> {
+        self.characters.get(name)
+    }
+}
+
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct HitboxSettings {
+    /// Pixels the deadly obstacle hitboxes are shrunk by inside their sprite, so that grazing a
+    /// corner is forgiven.
+    pub forgiveness: f32,
+}
+
+impl Default for HitboxSettings {
+    fn default() -> Self {
+        Self {
+            forgiveness: HITBOX_FORGIVENESS,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::{tests::Scenario, PhysicsBody};
+
+    /// Every shape the game builds colliders from: the definitions as the player flies them, and
+    /// the pipes shrunk by the forgiveness, standing up or hanging down.
+    fn every_hitbox() -> Vec<(String, Hitbox)> {
+        let definitions = HitboxDefinitions::default();
+        let pipe = definitions.pipe.shrunk(HITBOX_FORGIVENESS);
+        let mut hitboxes: Vec<(String, Hitbox)> = definitions
+            .characters
+            .iter()
+            .map(|(name, hitbox)| (name.clone(), hitbox.clone()))
+            .collect();
+        hitboxes.push(("pipe".to_string(), pipe.clone()));
+        hitboxes.push(("hanging pipe".to_string(), pipe.flipped_y()));
+        hitboxes
+    }
+
+    fn assert_convex(name: &str, hitbox: &Hitbox) {
+        match hitbox {
+            Hitbox::Circle { radius } => assert!(*radius > 0.0, "{name} has an empty circle"),
+            Hitbox::Rect { half_extents } => {
+                assert!(half_extents.min_element() > 0.0, "{name} has an empty rect")
+            }
+            Hitbox::Polygon { points } => {
+                let count = points.len();
+                assert!(count >= 3, "{name} has a polygon of {count} points");
+                let turns: Vec<f32> = (0..count)
+                    .map(|i| {
+                        let (a, b, c) =
+                            (points[i], points[(i + 1) % count], points[(i + 2) % count]);
+                        (b - a).perp_dot(c - b)
+                    })
+                    .collect();
+                assert!(
+                    turns.iter().all(|turn| *turn > 0.0) || turns.iter().all(|turn| *turn < 0.0),
+                    "{name} has a polygon which is not strictly convex"
+                );
+            }
+            Hitbox::Compound { parts } => {
+                assert!(!parts.is_empty(), "{name} has an empty compound");
+                for (_, part) in parts {
+                    assert_convex(name, part);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hitboxes_are_convex_shapes() {
+        for (name, hitbox) in every_hitbox() {
+            assert_convex(&name, &hitbox);
+        }
+    }
+
+    #[test]
+    fn hitboxes_collide_on_the_physics_backend() {
+        for (name, hitbox) in every_hitbox() {
+            let mut scenario = Scenario::new(0.0);
+            // a sensor, so that the player is not pushed out of it
+            let body = scenario.spawn(Vec2::ZERO, PhysicsBody::fixed(hitbox).sensor());
+            scenario.start();
+            scenario.step();
+            scenario.assert_contacts(&name, &[(true, body, 1)]);
+        }
+    }
+}
@@ -1,45 +1,118 @@
-mod animations;
-mod app_states;
-mod assets;
-mod camera;
-mod consts;
-mod events;
-mod game;
-mod obstacles;
-mod player;
-mod ui;
-mod world;
+pub mod achievements;
+pub mod animations;
+pub mod app_states;
+pub mod assets;
+pub mod camera;
+pub mod characters;
+pub mod coins;
+pub mod console;
+pub mod consts;
+#[cfg(feature = "debug-overlay")]
+pub mod debug_overlay;
+pub mod events;
+pub mod game;
+pub mod hazards;
+pub mod history;
+pub mod hitboxes;
+pub mod medals;
+pub mod motion;
+pub mod mutators;
+pub mod obstacles;
+pub mod particles;
+pub mod persistence;
+pub mod physics;
+pub mod player;
+pub mod powerups;
+pub mod rewind;
+pub mod schedule;
+pub mod scoring;
+pub mod shop;
+pub mod stats;
+pub mod transitions;
+pub mod tweening;
+pub mod ui;
+pub mod world;
 
 // use bevy_prototype_lyon::prelude::*;
-use bevy::{prelude::*, window::PresentMode};
-use bevy_rapier2d::{
-    prelude::{NoUserData, RapierConfiguration, RapierPhysicsPlugin},
+use bevy::{
+    app::PluginGroupBuilder,
+    prelude::*,
+    window::{PresentMode, WindowResizeConstraints},
 };
+#[cfg(feature = "rapier")]
+use bevy_rapier2d::prelude::{NoUserData, RapierDebugRenderPlugin, RapierPhysicsPlugin};
 use bevy_turborand::prelude::*;
-use events::{
-    dispatch_collision_events, handle_game_event_player_hit_game_over_collider, GameEvent,
-};
-use iyes_loopless::prelude::*;
 
-use animations::animate_sprite;
-use app_states::{change_state_to_playing_on_input, AppState, InGameState, LaunchingState};
-use assets::{change_state_to_ingame_when_assets_loaded, load_game_assets};
-use camera::spawn_camera;
-use consts::{BASE_GAME_SPEED, GAME_HEIGHT, GAME_WIDTH, GRAVITY};
-use game::{ground_buffer_swap, move_game_elements_horizontal, update_game_speed, GameSpeed};
-use obstacles::{
-    despawn_passed_obstacles, reset_obstacles_state, spawn_obstacles, update_obstacles_data,
-    ObstaclesData,
-};
-use player::{
-    enable_player_gravity, handle_game_event_player_passed_opening, player_jump,
-    reset_player_score, reset_player_state, spawn_player, PlayerScore,
-};
-use ui::{
-    despawn_game_ready_label, despawn_game_score, spawn_game_ready_label, spawn_game_score,
-    update_player_score_label,
-};
-use world::spawn_world_ground;
+#[cfg(feature = "rapier")]
+use consts::PIXELS_PER_METER;
+use consts::{GAME_HEIGHT, GAME_WIDTH};
+
+pub use app_states::StatesPlugin;
+pub use camera::CameraPlugin;
+pub use coins::CoinsPlugin;
+pub use console::ConsolePlugin;
+pub use hazards::HazardsPlugin;
+pub use motion::MotionPlugin;
+pub use mutators::MutatorsPlugin;
+pub use obstacles::ObstaclesPlugin;
+pub use particles::ParticlesPlugin;
+pub use physics::PhysicsPlugin;
+pub use player::PlayerPlugin;
+pub use powerups::PowerUpsPlugin;
+pub use rewind::RewindPlugin;
+pub use schedule::GameSchedulePlugin;
+pub use scoring::ScoringPlugin;
+pub use shop::ShopPlugin;
+pub use ui::UiPlugin;
+pub use world::WorldPlugin;
+
+/// The whole game as a group of plugins. Hosts embedding it can disable, replace or add plugins
+/// around any of them, e.g. disable the rapier plugin when they already run one.
+///
+/// [`GameSchedulePlugin`], [`PhysicsPlugin`] and [`StatesPlugin`] register the stages, physics
+/// and states every other plugin relies on, they have to stay first.
+pub struct FlappyGamePlugin;
+
+impl PluginGroup for FlappyGamePlugin {
+    fn build(self) -> PluginGroupBuilder {
+        let group = PluginGroupBuilder::start::<Self>();
+        #[cfg(feature = "rapier")]
+        let group = group
+            .add(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(
+                PIXELS_PER_METER,
+            ))
+            .add(RapierDebugRenderPlugin {
+                enabled: false,
+                ..Default::default()
+            });
+        let group = group
+            .add(GameSchedulePlugin)
+            .add(PhysicsPlugin)
+            .add(RngPlugin::default())
+            .add(StatesPlugin)
+            .add(ConsolePlugin)
+            .add(WorldPlugin)
+            .add(CameraPlugin)
+            .add(PlayerPlugin)
+            .add(MotionPlugin)
+            .add(ObstaclesPlugin)
+            .add(HazardsPlugin)
+            .add(PowerUpsPlugin)
+            .add(CoinsPlugin)
+            .add(ShopPlugin)
+            .add(MutatorsPlugin)
+            .add(ScoringPlugin)
+            .add(RewindPlugin)
+            .add(ParticlesPlugin)
+            .add(UiPlugin);
+        #[cfg(feature = "debug-overlay")]
+        let group = group.add(debug_overlay::DebugOverlayPlugin);
+        group
+    }
+}
+
+// the window opens at twice the virtual resolution, then scales by whole factors when resized
+const INITIAL_WINDOW_SCALE: f32 = 2.0;
 
 pub struct BuildGameAppData {
     pub canvas: Option<String>,
@@ -48,116 +121,28 @@ pub struct BuildGameAppData {
 
 pub fn build_game_app(data: BuildGameAppData) -> App {
     let mut app = App::new();
-    app.insert_resource(ClearColor(Color::hex("dff6f5").unwrap()))
-        .insert_resource(GameSpeed {
-            factor: BASE_GAME_SPEED,
-        })
-        .insert_resource(RapierConfiguration {
-            gravity: Vec2::new(0.0, GRAVITY),
-            ..Default::default()
-        })
-        .init_resource::<ObstaclesData>()
-        .init_resource::<PlayerScore>()
-        .add_event::<GameEvent>()
-        .add_plugins(
-            DefaultPlugins
-                .set(WindowPlugin {
-                    window: WindowDescriptor {
-                        title: data.window_title.unwrap_or("Flappy Bevy".to_string()),
-                        height: GAME_HEIGHT,
-                        width: GAME_WIDTH,
-                        canvas: data.canvas,
-                        present_mode: PresentMode::AutoVsync,
+    app.add_plugins(
+        DefaultPlugins
+            .set(WindowPlugin {
+                window: WindowDescriptor {
+                    title: data.window_title.unwrap_or("Flappy Bevy".to_string()),
+                    height: GAME_HEIGHT * INITIAL_WINDOW_SCALE,
+                    width: GAME_WIDTH * INITIAL_WINDOW_SCALE,
+                    resize_constraints: WindowResizeConstraints {
+                        min_width: GAME_WIDTH,
+                        min_height: GAME_HEIGHT,
                         ..Default::default()
                     },
+                    canvas: data.canvas,
+                    // the browser canvas follows the size of its parent element
+                    fit_canvas_to_parent: true,
+                    present_mode: PresentMode::AutoVsync,
                     ..Default::default()
-                })
-                .set(ImagePlugin::default_nearest()),
-        )
-        .add_plugin(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(36.0))
-        // .add_plugin(RapierDebugRenderPlugin::default())
-        .add_plugin(RngPlugin::default())
-        .add_loopless_state(AppState::Launching(LaunchingState::Loading))
-        // LAUNCHING - LOADING
-        .add_startup_system(load_game_assets)
-        .add_system_set(
-            ConditionSet::new()
-                .run_in_state(AppState::Launching(LaunchingState::Loading))
-                .with_system(change_state_to_ingame_when_assets_loaded)
-                .into(),
-        )
-        // LAUNCHING - READY
-        //
-        // IN GAME - INITIALIZATION
-        .add_enter_system_set(
-            AppState::InGame(InGameState::Initialization),
-            ConditionSet::new()
-                .with_system(spawn_world_ground)
-                .with_system(spawn_player)
-                .with_system(spawn_camera)
-                .into(),
-        )
-        .add_system_set(
-            ConditionSet::new()
-                .run_in_state(AppState::InGame(InGameState::Initialization))
-                .with_system(change_state_to_ready_to_start)
-                .into(),
-        )
-        // IN GAME - READY TO START
-        .add_enter_system_set(
-            AppState::InGame(InGameState::ReadyToStart),
-            ConditionSet::new()
-                .with_system(reset_player_state)
-                .with_system(reset_player_score)
-                .with_system(reset_obstacles_state)
-                .with_system(spawn_game_ready_label)
-                .into(),
-        )
-        .add_system_set(
-            ConditionSet::new()
-                .run_in_state(AppState::InGame(InGameState::ReadyToStart))
-                .with_system(change_state_to_playing_on_input)
-                .with_system(animate_sprite)
-                .into(),
-        )
-        .add_exit_system_set(
-            AppState::InGame(InGameState::ReadyToStart),
-            ConditionSet::new()
-                .with_system(despawn_game_ready_label)
-                .into(),
-        )
-        // IN GAME - PLAYING
-        .add_enter_system_set(
-            AppState::InGame(InGameState::Playing),
-            ConditionSet::new()
-                .with_system(enable_player_gravity)
-                .with_system(spawn_game_score)
-                .into(),
-        )
-        .add_system_set(
-            ConditionSet::new()
-                .run_in_state(AppState::InGame(InGameState::Playing))
-                .with_system(animate_sprite)
-                .with_system(move_game_elements_horizontal)
-                .with_system(update_game_speed)
-                .with_system(ground_buffer_swap)
-                .with_system(player_jump)
-                .with_system(update_obstacles_data)
-                .with_system(spawn_obstacles)
-                .with_system(despawn_passed_obstacles)
-                .with_system(update_player_score_label)
-                .with_system(dispatch_collision_events)
-                .with_system(handle_game_event_player_hit_game_over_collider)
-                .with_system(handle_game_event_player_passed_opening)
-                .into(),
-        )
-        .add_exit_system_set(
-            AppState::InGame(InGameState::Playing),
-            ConditionSet::new().with_system(despawn_game_score).into(),
-        );
+                },
+                ..Default::default()
+            })
+            .set(ImagePlugin::default_nearest()),
+    )
+    .add_plugins(FlappyGamePlugin);
     app
 }
-
-fn change_state_to_ready_to_start(mut commands: Commands) {
-    commands.insert_resource(NextState(AppState::InGame(InGameState::ReadyToStart)));
-}
@@ -0,0 +1,69 @@
+use bevy::prelude::Resource;
+use serde::{Deserialize, Serialize};
+
+use crate::consts::{
+    MEDAL_BRONZE_SCORE, MEDAL_GOLD_SCORE, MEDAL_PLATINUM_SCORE, MEDAL_SILVER_SCORE,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Medal {
+    Bronze,
+    Silver,
+    Gold,
+    Platinum,
+}
+
+impl Medal {
+    pub fn atlas_index(&self) -> usize {
+        match self {
+            Medal::Bronze => 0,
+            Medal::Silver => 1,
+            Medal::Gold => 2,
+            Medal::Platinum => 3,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Medal::Bronze => "Bronze",
+            Medal::Silver => "Silver",
+            Medal::Gold => "Gold",
+            Medal::Platinum => "Platinum",
+        }
+    }
+}
+
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct MedalThresholds {
+    pub bronze: u16,
+    pub silver: u16,
+    pub gold: u16,
+    pub platinum: u16,
+}
+
+impl Default for MedalThresholds {
+    fn default() -> Self {
+        Self {
+            bronze: MEDAL_BRONZE_SCORE,
+            silver: MEDAL_SILVER_SCORE,
+            gold: MEDAL_GOLD_SCORE,
+            platinum: MEDAL_PLATINUM_SCORE,
+        }
+    }
+}
+
+impl MedalThresholds {
+    pub fn medal_for(&self, score: u16) -> Option<Medal> {
+        if score >= self.platinum {
+            Some(Medal::Platinum)
+        } else if score >= self.gold {
+            Some(Medal::Gold)
+        } else if score >= self.silver {
+            Some(Medal::Silver)
+        } else if score >= self.bronze {
+            Some(Medal::Bronze)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use bevy::prelude::{
+    App, Commands, Entity, EulerRot, EventReader, Input, KeyCode, Parent, Plugin, Quat, Query, Res,
+    ResMut, Resource, Transform, Vec3, With,
+};
+use iyes_loopless::prelude::{AppLooplessStateExt, ConditionSet, IntoConditionalSystem};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_states::{AppState, InGameState},
+    assets::GameAssets,
+    events::GameEvent,
+    game::GameTime,
+    persistence::{self, Profile},
+    physics::Velocity,
+    player::{PlayerCharacter, PlayerSprite},
+    schedule::GameStage,
+    transitions::no_screen_transition,
+    tweening::{Ease, Tween, TweenTarget},
+    ui::{free_toast_slot, spawn_toast, Toast},
+};
+
+const MOTION_SETTINGS_KEY: &str = "motion";
+const REDUCED_MOTION_TOGGLE_KEY: KeyCode = KeyCode::M;
+
+/// Tilt of the player sprite following its vertical velocity and squash and stretch on each
+/// jump. Visual only, the collider keeps still.
+pub struct MotionPlugin;
+
+impl Plugin for MotionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Profile>()
+            .init_resource::<MotionSettings>()
+            .add_startup_system(load_motion_settings)
+            .add_system(
+                toggle_reduced_motion_on_input
+                    .run_in_state(AppState::InGame(InGameState::ReadyToStart)),
+            )
+            .add_enter_system(
+                AppState::InGame(InGameState::ReadyToStart),
+                reset_player_sprite_motion,
+            )
+            .add_system_set_to_stage(
+                GameStage::Ui,
+                ConditionSet::new()
+                    .run_in_state(AppState::InGame(InGameState::Playing))
+                    .run_if(no_screen_transition)
+                    .with_system(tilt_player_sprite)
+                    .with_system(stretch_player_sprite_on_flap)
+                    .into(),
+            );
+    }
+}
+
+/// Motion effects tuning, persisted under the current [`Profile`].
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MotionSettings {
+    /// Turns the motion effects off.
+    pub reduced_motion: bool,
+    /// Tilt up when rising at the jump speed.
+    pub tilt_up_degrees: f32,
+    /// Tilt down when falling at `dive_velocity` or faster.
+    pub tilt_down_degrees: f32,
+    pub dive_velocity: f32,
+    /// How fast the tilt catches up with the velocity, higher is snappier.
+    pub tilt_response: f32,
+    /// Vertical stretch of the sprite on a jump, the width shrinks by half as much.
+    pub flap_stretch: f32,
+    pub flap_stretch_seconds: f32,
+}
+
+impl Default for MotionSettings {
+    fn default() -> Self {
+        Self {
+            reduced_motion: false,
+            tilt_up_degrees: 25.0,
+            tilt_down_degrees: 80.0,
+            dive_velocity: 420.0,
+            tilt_response: 12.0,
+            flap_stretch: 0.25,
+            flap_stretch_seconds: 0.2,
+        }
+    }
+}
+
+impl MotionSettings {
+    /// Target tilt in radians for a vertical velocity, `rise_velocity` being the jump speed.
+    fn tilt_for(&self, velocity: f32, rise_velocity: f32) -> f32 {
+        if velocity >= 0.0 {
+            self.tilt_up_degrees.to_radians() * (velocity / rise_velocity.max(1.0)).min(1.0)
+        } else {
+            -self.tilt_down_degrees.to_radians()
+                * (-velocity / self.dive_velocity.max(1.0)).min(1.0)
+        }
+    }
+}
+
+pub fn load_motion_settings(mut commands: Commands, profile: Res<Profile>) {
+    commands.insert_resource(persistence::load_or_default::<MotionSettings>(
+        &profile,
+        MOTION_SETTINGS_KEY,
+    ));
+}
+
+pub fn toggle_reduced_motion_on_input(
+    mut commands: Commands,
+    mut settings: ResMut<MotionSettings>,
+    keyboard_input: Res<Input<KeyCode>>,
+    profile: Res<Profile>,
+    game_assets: Res<GameAssets>,
+    q_toasts: Query<&Toast>,
+) {
+    if !keyboard_input.just_pressed(REDUCED_MOTION_TOGGLE_KEY) {
+        return;
+    }
+    settings.reduced_motion = !settings.reduced_motion;
+    persistence::save(&profile, MOTION_SETTINGS_KEY, &*settings);
+    spawn_toast(
+        &mut commands,
+        game_assets.font.clone(),
+        "Reduced motion",
+        if settings.reduced_motion { "On" } else { "Off" },
+        free_toast_slot(&q_toasts),
+    );
+}
+
+pub fn reset_player_sprite_motion(
+    mut commands: Commands,
+    mut q_sprite: Query<(Entity, &mut Transform), With<PlayerSprite>>,
+) {
+    for (e, mut t) in q_sprite.iter_mut() {
+        commands.entity(e).remove::<Tween>();
+        t.rotation = Quat::IDENTITY;
+        t.scale = Vec3::ONE;
+    }
+}
+
+pub fn tilt_player_sprite(
+    mut q_sprite: Query<(&mut Transform, &Parent), With<PlayerSprite>>,
+    q_player: Query<(&Velocity, &PlayerCharacter)>,
+    settings: Res<MotionSettings>,
+    game_time: Res<GameTime>,
+) {
+    for (mut t, parent) in q_sprite.iter_mut() {
+        if settings.reduced_motion {
+            t.rotation = Quat::IDENTITY;
+            continue;
+        }
+        if let Ok((velocity, character)) = q_player.get(parent.get()) {
+            let target = settings.tilt_for(velocity.0.y, character.jump_impulse);
+            let (angle, _, _) = t.rotation.to_euler(EulerRot::ZYX);
+            let catch_up = 1.0 - (-settings.tilt_response * game_time.delta_seconds()).exp();
+            t.rotation = Quat::from_rotation_z(angle + (target - angle) * catch_up);
+        }
+    }
+}
+
+pub fn stretch_player_sprite_on_flap(
+    mut commands: Commands,
+    mut ev_game: EventReader<GameEvent>,
+    q_sprite: Query<Entity, With<PlayerSprite>>,
+    settings: Res<MotionSettings>,
+) {
+    let flapped = ev_game
+        .iter()
+        .filter(|ev| matches!(ev, GameEvent::PlayerFlapped { .. }))
+        .count()
+        > 0;
+    if !flapped || settings.reduced_motion || settings.flap_stretch <= 0.0 {
+        return;
+    }
+    for e in q_sprite.iter() {
+        commands.entity(e).insert(
+            Tween::new(settings.flap_stretch_seconds, Ease::QuadOut).with(TweenTarget::Scale {
+                start: Vec3::new(
+                    1.0 - settings.flap_stretch / 2.0,
+                    1.0 + settings.flap_stretch,
+                    1.0,
+                ),
+                end: Vec3::ONE,
+            }),
+        );
+    }
+}
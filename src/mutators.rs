@@ -0,0 +1,258 @@
+use std::collections::BTreeSet;
+
+use bevy::{
+    prelude::{
+        App, Commands, Component, Entity, EventReader, EventWriter, Input, IntoSystemDescriptor,
+        KeyCode, Plugin, Query, Res, ResMut, Resource, Vec2, With,
+    },
+    sprite::TextureAtlasSprite,
+};
+use iyes_loopless::prelude::{AppLooplessStateExt, ConditionSet, IntoConditionalSystem};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_states::{AppState, InGameState, MenuState},
+    camera::{CameraController, MainCamera},
+    consts::{GRAVITY, PLAYER_SPRITE_SIZE},
+    events::GameEvent,
+    game::{reset_game_speed, GameSpeed},
+    obstacles::ObstacleSettings,
+    persistence::{self, Profile},
+    physics::{Gravity, Hitbox, PhysicsBody},
+    player::{FlapBudget, Player, PlayerSprite},
+    schedule::GameStage,
+    transitions::{no_screen_transition, StateChangeRequest},
+};
+
+const MUTATORS_KEY: &str = "mutators";
+
+const LOW_GRAVITY_FACTOR: f32 = 0.55;
+const DOUBLE_SPEED_FACTOR: f32 = 2.0;
+const TINY_OPENINGS_FACTOR: f32 = 0.7;
+const BIG_HEAD_SCALE: f32 = 1.5;
+const FLAPS_PER_PIPE: u32 = 1;
+
+/// Run mutators picked from the ready screen, any of them can be combined. Runs are recorded
+/// with their mutators, each combination being ranked apart.
+pub struct MutatorsPlugin;
+
+impl Plugin for MutatorsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Profile>()
+            .init_resource::<Mutators>()
+            .add_startup_system(load_mutators)
+            .add_enter_system(
+                AppState::InGame(InGameState::ReadyToStart),
+                apply_mutators.after(reset_game_speed),
+            )
+            .add_enter_system(AppState::InGame(InGameState::Playing), start_mutated_run)
+            .add_system_set_to_stage(
+                GameStage::Scoring,
+                ConditionSet::new()
+                    .run_in_state(AppState::InGame(InGameState::Playing))
+                    .run_if(no_screen_transition)
+                    .with_system(refill_flap_budget)
+                    .into(),
+            )
+            .add_system_to_stage(GameStage::Ui, apply_big_head)
+            // MENU - MUTATORS
+            .add_enter_system(AppState::Menu(MenuState::Mutators), start_mutator_select)
+            .add_system(
+                browse_mutators_on_input
+                    .run_in_state(AppState::Menu(MenuState::Mutators))
+                    .run_if(no_screen_transition),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Mutator {
+    LowGravity,
+    InvertedGravity,
+    DoubleSpeed,
+    TinyOpenings,
+    MirroredScrolling,
+    BigHead,
+    OneFlapPerPipe,
+}
+
+impl Mutator {
+    pub const ALL: [Mutator; 7] = [
+        Mutator::LowGravity,
+        Mutator::InvertedGravity,
+        Mutator::DoubleSpeed,
+        Mutator::TinyOpenings,
+        Mutator::MirroredScrolling,
+        Mutator::BigHead,
+        Mutator::OneFlapPerPipe,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Mutator::LowGravity => "Low gravity",
+            Mutator::InvertedGravity => "Inverted gravity",
+            Mutator::DoubleSpeed => "Double speed",
+            Mutator::TinyOpenings => "Tiny openings",
+            Mutator::MirroredScrolling => "Mirrored",
+            Mutator::BigHead => "Big head",
+            Mutator::OneFlapPerPipe => "One flap per pipe",
+        }
+    }
+}
+
+/// The mutators of the next runs, persisted under the current [`Profile`]. It is also the
+/// category of a recorded run.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Mutators(BTreeSet<Mutator>);
+
+impl Mutators {
+    pub fn is_active(&self, mutator: Mutator) -> bool {
+        self.0.contains(&mutator)
+    }
+
+    pub fn is_standard(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn toggle(&mut self, mutator: Mutator) {
+        if !self.0.remove(&mutator) {
+            self.0.insert(mutator);
+        }
+    }
+
+    pub fn label(&self) -> String {
+        if self.is_standard() {
+            return "Standard".to_string();
+        }
+        self.0
+            .iter()
+            .map(|mutator| mutator.label())
+            .collect::<Vec<_>>()
+            .join(" + ")
+    }
+}
+
+/// Index in [`Mutator::ALL`] highlighted on the mutators screen.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct MutatorCursor(pub usize);
+
+/// The hitbox of the player before its head grew.
+#[derive(Component)]
+pub struct BigHead(Hitbox);
+
+pub fn load_mutators(mut commands: Commands, profile: Res<Profile>) {
+    commands.insert_resource(persistence::load_or_default::<Mutators>(
+        &profile,
+        MUTATORS_KEY,
+    ));
+}
+
+/// Sets up the world for the mutators of the next run, the ready screen already showing them.
+/// Practice retries start playing again without this setup, and keep the pace of their run.
+pub fn apply_mutators(
+    mutators: Res<Mutators>,
+    mut game_speed: ResMut<GameSpeed>,
+    mut gravity: ResMut<Gravity>,
+    mut obstacle_settings: ResMut<ObstacleSettings>,
+    mut q_camera: Query<&mut CameraController, With<MainCamera>>,
+) {
+    let mut gravity_y = GRAVITY;
+    if mutators.is_active(Mutator::LowGravity) {
+        gravity_y *= LOW_GRAVITY_FACTOR;
+    }
+    if mutators.is_active(Mutator::InvertedGravity) {
+        gravity_y = -gravity_y;
+    }
+    gravity.0 = Vec2::new(0.0, gravity_y);
+
+    if mutators.is_active(Mutator::DoubleSpeed) {
+        game_speed.factor *= DOUBLE_SPEED_FACTOR;
+    }
+
+    *obstacle_settings = ObstacleSettings::default();
+    if mutators.is_active(Mutator::TinyOpenings) {
+        obstacle_settings.opening_height *= TINY_OPENINGS_FACTOR;
+    }
+
+    if let Ok(mut controller) = q_camera.get_single_mut() {
+        controller.mirrored = mutators.is_active(Mutator::MirroredScrolling);
+    }
+}
+
+pub fn start_mutated_run(mutators: Res<Mutators>, mut flap_budget: ResMut<FlapBudget>) {
+    flap_budget.0 = mutators
+        .is_active(Mutator::OneFlapPerPipe)
+        .then_some(FLAPS_PER_PIPE);
+}
+
+pub fn refill_flap_budget(
+    mut ev_game: EventReader<GameEvent>,
+    mut flap_budget: ResMut<FlapBudget>,
+) {
+    for ev in ev_game.iter() {
+        if let (GameEvent::PlayerPassedAnOpening { .. }, Some(flaps)) = (ev, flap_budget.0.as_mut())
+        {
+            *flaps = FLAPS_PER_PIPE;
+        }
+    }
+}
+
+/// Grows the player sprite and its hitbox while the big head mutator is on, whichever character
+/// is flown.
+pub fn apply_big_head(
+    mut commands: Commands,
+    mut q_player: Query<(Entity, &mut PhysicsBody, Option<&BigHead>), With<Player>>,
+    mut q_sprite: Query<&mut TextureAtlasSprite, With<PlayerSprite>>,
+    mutators: Res<Mutators>,
+) {
+    let big_head = mutators.is_active(Mutator::BigHead);
+    for (player, mut body, grown) in q_player.iter_mut() {
+        match (big_head, grown) {
+            (true, None) => {
+                commands.entity(player).insert(BigHead(body.hitbox.clone()));
+                body.hitbox = body.hitbox.scaled(BIG_HEAD_SCALE);
+                for mut sprite in q_sprite.iter_mut() {
+                    sprite.custom_size = Some(Vec2::splat(PLAYER_SPRITE_SIZE * BIG_HEAD_SCALE));
+                }
+            }
+            (false, Some(BigHead(hitbox))) => {
+                body.hitbox = hitbox.clone();
+                commands.entity(player).remove::<BigHead>();
+                for mut sprite in q_sprite.iter_mut() {
+                    sprite.custom_size = None;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn start_mutator_select(mut commands: Commands) {
+    commands.insert_resource(MutatorCursor(0));
+}
+
+pub fn browse_mutators_on_input(
+    mut ev_state: EventWriter<StateChangeRequest>,
+    mut cursor: ResMut<MutatorCursor>,
+    mut mutators: ResMut<Mutators>,
+    profile: Res<Profile>,
+    keyboard_input: Res<Input<KeyCode>>,
+) {
+    let count = Mutator::ALL.len();
+    if keyboard_input.any_just_pressed([KeyCode::Up, KeyCode::W]) {
+        cursor.0 = (cursor.0 + count - 1) % count;
+    }
+    if keyboard_input.any_just_pressed([KeyCode::Down, KeyCode::S]) {
+        cursor.0 = (cursor.0 + 1) % count;
+    }
+    if keyboard_input.any_just_pressed([KeyCode::Space, KeyCode::Return]) {
+        mutators.toggle(Mutator::ALL[cursor.0]);
+        persistence::save(&profile, MUTATORS_KEY, &*mutators);
+    }
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        ev_state.send(StateChangeRequest(AppState::InGame(
+            InGameState::ReadyToStart,
+        )));
+    }
+}
@@ -1,22 +1,29 @@
 use bevy::{
     prelude::{
-        Commands, Component, Entity, Handle, Image, Name, Query, Res, ResMut, Resource, Transform,
-        Vec2, Vec3,
+        App, Commands, Component, Entity, EventReader, EventWriter, Handle, Image, Name, Plugin,
+        Query, Res, ResMut, Resource, Transform, Vec2, Vec3,
     },
     sprite::{Anchor, Sprite, SpriteBundle},
-    time::Time,
     transform::TransformBundle,
 };
-use bevy_rapier2d::prelude::{Collider, CollisionGroups, GravityScale, RigidBody, Sensor};
 use bevy_turborand::{DelegatedRng, GlobalRng};
+use iyes_loopless::prelude::{AppLooplessStateExt, ConditionSet};
 
 use crate::{
+    app_states::{AppState, InGameState},
     assets::GameAssets,
+    coins::spawn_coin_arc,
+    console::{ConsoleCommand, ConsoleCommandInfo, ConsoleLine, RegisterConsoleCommands},
     consts::{
-        BASE_MOVE_SPEED, COLLISION_GROUP_GAME_OVER, COLLISION_GROUP_OPENING,
-        COLLISION_GROUP_PLAYER, GAME_HEIGHT, GAME_WIDTH,
+        BASE_MOVE_SPEED, COLLISION_GROUP_OPENING, COLLISION_GROUP_PIPE, COLLISION_GROUP_PLAYER,
+        GAME_HEIGHT, GAME_WIDTH,
     },
-    game::{GameSpeed, HorizontalMove},
+    events::DeathCause,
+    game::{GameSpeed, GameTime, HorizontalMove},
+    hitboxes::{HitboxDefinitions, HitboxSettings},
+    physics::{Hitbox, PhysicsBody},
+    schedule::GameStage,
+    transitions::no_screen_transition,
 };
 
 const OBSTACLE_SPRITE_WIDTH: f32 = 18.0;
@@ -30,10 +37,90 @@ const OBSTACLE_GAP_MAX_HORIZONTAL_DISTANCE: f32 = 130.0;
 
 const OBSTACLE_OPENING_HEIGHT: f32 = 80.0;
 
-#[derive(Default, Resource)]
+const OBSTACLE_CONSOLE_COMMANDS: [ConsoleCommandInfo; 2] = [
+    ConsoleCommandInfo {
+        name: "seed",
+        args: "<n>",
+        help: "reseed the obstacles generation",
+        completions: &[],
+    },
+    ConsoleCommandInfo {
+        name: "spawn",
+        args: "<obstacle>",
+        help: "spawn an obstacle at the right edge",
+        completions: &["pipe"],
+    },
+];
+
+/// Spawns the pipes, their opening sensors and the coins through them as the world scrolls.
+pub struct ObstaclesPlugin;
+
+impl Plugin for ObstaclesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ObstacleSpawned>()
+            .init_resource::<ObstaclesData>()
+            .init_resource::<ObstacleSettings>()
+            .init_resource::<HitboxDefinitions>()
+            .init_resource::<HitboxSettings>()
+            .register_console_commands(OBSTACLE_CONSOLE_COMMANDS)
+            .add_system(handle_obstacle_console_commands)
+            .add_enter_system(
+                AppState::InGame(InGameState::ReadyToStart),
+                reset_obstacles_state,
+            )
+            .add_system_set_to_stage(
+                GameStage::Movement,
+                ConditionSet::new()
+                    .run_in_state(AppState::InGame(InGameState::Playing))
+                    .run_if(no_screen_transition)
+                    .with_system(update_obstacles_data)
+                    .with_system(spawn_obstacles)
+                    .with_system(despawn_passed_obstacles)
+                    .into(),
+            );
+    }
+}
+
+/// Sent each time an obstacle is spawned, for the hazards and pickups placed along the obstacles
+/// to roll for their own spawn.
+#[derive(Debug, Clone, Copy)]
+pub struct ObstacleSpawned;
+
+#[derive(Default, Resource, Debug, Clone, Copy)]
 pub struct ObstaclesData {
     traveled_distance: f32,
     last_obstacle_distance: f32,
+    spawned_obstacles: u32,
+}
+
+impl ObstaclesData {
+    pub fn traveled_distance(&self) -> f32 {
+        self.traveled_distance
+    }
+
+    /// Distance traveled since the last obstacle was spawned.
+    pub fn last_obstacle_distance(&self) -> f32 {
+        self.last_obstacle_distance
+    }
+
+    /// Obstacles spawned during the run, it goes down when the world is rewound.
+    pub fn spawned_obstacles(&self) -> u32 {
+        self.spawned_obstacles
+    }
+}
+
+/// Shape of the obstacles to come, changed by the run mutators.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct ObstacleSettings {
+    pub opening_height: f32,
+}
+
+impl Default for ObstacleSettings {
+    fn default() -> Self {
+        Self {
+            opening_height: OBSTACLE_OPENING_HEIGHT,
+        }
+    }
 }
 
 #[derive(Component, Eq, PartialEq, Debug, Clone, Copy)]
@@ -41,41 +128,103 @@ pub enum Obstacle {
     GameOver,
     GameOverStatic,
     Opening,
+    /// Bird or rock of the [`HazardsPlugin`](crate::hazards::HazardsPlugin), which handles
+    /// its collisions.
+    Hazard,
+}
+
+/// Progress of the player through an opening sensor, so each opening scores at most once.
+#[derive(Component, Default, Debug, Clone, Copy)]
+pub struct OpeningCrossing {
+    pub entered: bool,
+    pub scored: bool,
+}
+
+/// Vertical room left between the bounding box of a body hitbox and the edges of an opening
+/// `opening_height` tall, `None` when the body is not horizontally within the opening.
+pub fn opening_clearance(
+    opening: Vec3,
+    opening_height: f32,
+    body: Vec3,
+    body_hitbox: &Hitbox,
+) -> Option<f32> {
+    let (min, max) = body_hitbox.aabb();
+    let (min, max) = (body.truncate() + min, body.truncate() + max);
+    if max.x < opening.x - OBSTACLE_SPRITE_WIDTH / 2.0
+        || min.x > opening.x + OBSTACLE_SPRITE_WIDTH / 2.0
+    {
+        return None;
+    }
+    let bottom_clearance = min.y - (opening.y - opening_height / 2.0);
+    let top_clearance = (opening.y + opening_height / 2.0) - max.y;
+    Some(bottom_clearance.min(top_clearance))
+}
+
+/// Lowest bottom and highest top of the openings, the heights the player is expected to fly at.
+pub fn opening_band() -> (f32, f32) {
+    (
+        -GAME_HEIGHT / 2.0 + OBSTACLE_OPENING_MIN_BOTTOM_Y_OFFSET,
+        GAME_HEIGHT / 2.0 - OBSTACLE_OPENING_MAX_TOP_Y_OFFSET,
+    )
+}
+
+/// Whether something spanning `min_y` to `max_y` still leaves room for a standard opening above or
+/// below it within the [`opening_band`].
+pub fn leaves_passable_corridor(min_y: f32, max_y: f32) -> bool {
+    let (bottom, top) = opening_band();
+    min_y - bottom >= OBSTACLE_OPENING_HEIGHT || top - max_y >= OBSTACLE_OPENING_HEIGHT
 }
 
 pub fn update_obstacles_data(
     mut obstacle_data: ResMut<ObstaclesData>,
     game_speed: Res<GameSpeed>,
-    time: Res<Time>,
+    game_time: Res<GameTime>,
 ) {
-    let frame_traveled_distance = BASE_MOVE_SPEED * game_speed.factor * time.delta_seconds();
+    let frame_traveled_distance = BASE_MOVE_SPEED * game_speed.factor * game_time.delta_seconds();
     obstacle_data.traveled_distance += frame_traveled_distance;
     obstacle_data.last_obstacle_distance += frame_traveled_distance;
 }
 
 pub fn spawn_obstacles(
     mut commands: Commands,
+    mut ev_obstacles: EventWriter<ObstacleSpawned>,
     mut global_rng: ResMut<GlobalRng>,
     mut obstacles_data: ResMut<ObstaclesData>,
     game_assets: Res<GameAssets>,
+    hitboxes: Res<HitboxDefinitions>,
+    hitbox_settings: Res<HitboxSettings>,
+    obstacle_settings: Res<ObstacleSettings>,
 ) {
+    let opening_height = obstacle_settings.opening_height;
     if obstacles_data.last_obstacle_distance > OBSTACLE_GAP_MIN_HORIZONTAL_DISTANCE {
         let screen_offset_x = GAME_WIDTH / 2.0;
         let gap_offset_x = random_gap(&mut global_rng);
         let obstacle_pos_x = screen_offset_x + gap_offset_x;
 
-        let obstacle_opening_bottom_y_pos = random_opening_position_bottom_y(&mut global_rng);
+        let obstacle_opening_bottom_y_pos =
+            random_opening_position_bottom_y(&mut global_rng, opening_height);
 
         spawn_obstacle_entities(
             &mut commands,
             game_assets.obstacle_image.clone(),
+            &hitboxes.pipe.shrunk(hitbox_settings.forgiveness),
             obstacle_pos_x,
             obstacle_opening_bottom_y_pos,
+            opening_height,
+        );
+        spawn_coin_arc(
+            &mut commands,
+            Vec2::new(
+                obstacle_pos_x,
+                obstacle_opening_bottom_y_pos + opening_height / 2.0,
+            ),
         );
 
         obstacles_data.last_obstacle_distance = obstacles_data.last_obstacle_distance
             - OBSTACLE_GAP_MIN_HORIZONTAL_DISTANCE
             - gap_offset_x;
+        obstacles_data.spawned_obstacles += 1;
+        ev_obstacles.send(ObstacleSpawned);
     }
 }
 
@@ -99,23 +248,68 @@ pub fn reset_obstacles_state(
 ) {
     for (e, &o) in q_obstacles.iter() {
         match o {
-            Obstacle::GameOver | Obstacle::Opening => commands.entity(e).despawn(),
+            Obstacle::GameOver | Obstacle::Opening | Obstacle::Hazard => {
+                commands.entity(e).despawn()
+            }
             _ => {}
         }
     }
     *obstacles_data = ObstaclesData::default();
 }
 
+pub fn handle_obstacle_console_commands(
+    mut commands: Commands,
+    mut ev_commands: EventReader<ConsoleCommand>,
+    mut ev_console: EventWriter<ConsoleLine>,
+    mut global_rng: ResMut<GlobalRng>,
+    game_assets: Res<GameAssets>,
+    hitboxes: Res<HitboxDefinitions>,
+    hitbox_settings: Res<HitboxSettings>,
+    obstacle_settings: Res<ObstacleSettings>,
+) {
+    let opening_height = obstacle_settings.opening_height;
+    for command in ev_commands.iter() {
+        let result = match command.name.as_str() {
+            "seed" => command.arg::<u64>(0).map(|seed| {
+                *global_rng = GlobalRng::with_seed(seed);
+                format!("obstacles seed set to {}", seed)
+            }),
+            "spawn" => match command.arg::<String>(0).as_deref() {
+                Ok("pipe") => {
+                    let obstacle_opening_bottom_y_pos =
+                        random_opening_position_bottom_y(&mut global_rng, opening_height);
+                    spawn_obstacle_entities(
+                        &mut commands,
+                        game_assets.obstacle_image.clone(),
+                        &hitboxes.pipe.shrunk(hitbox_settings.forgiveness),
+                        (GAME_WIDTH + OBSTACLE_SPRITE_WIDTH) / 2.0,
+                        obstacle_opening_bottom_y_pos,
+                        opening_height,
+                    );
+                    Ok("pipe spawned".to_string())
+                }
+                Ok(obstacle) => Err(format!("unknown obstacle `{}`", obstacle)),
+                Err(err) => Err(err.clone()),
+            },
+            _ => continue,
+        };
+        ev_console.send(result.into());
+    }
+}
+
 fn spawn_obstacle_entities(
     commands: &mut Commands,
     obstacle_image_handle: Handle<Image>,
+    pipe_hitbox: &Hitbox,
     obstacle_pos_x: f32,
     obstacle_opening_bottom_y_pos: f32,
+    opening_height: f32,
 ) {
     let sprite_height_offset_y = OBSTACLE_SPRITE_HEIGHT / 2.0;
     spawn_obstacle_entity(
         commands,
         obstacle_image_handle.clone(),
+        pipe_hitbox.clone(),
         Vec2::new(
             obstacle_pos_x,
             obstacle_opening_bottom_y_pos - sprite_height_offset_y,
@@ -125,9 +319,10 @@ fn spawn_obstacle_entities(
     spawn_obstacle_entity(
         commands,
         obstacle_image_handle,
+        pipe_hitbox.flipped_y(),
         Vec2::new(
             obstacle_pos_x,
-            obstacle_opening_bottom_y_pos + sprite_height_offset_y + OBSTACLE_OPENING_HEIGHT,
+            obstacle_opening_bottom_y_pos + sprite_height_offset_y + opening_height,
         ),
         true,
     );
@@ -136,19 +331,18 @@ fn spawn_obstacle_entities(
         commands,
         Vec2::new(
             obstacle_pos_x,
-            obstacle_opening_bottom_y_pos + OBSTACLE_OPENING_HEIGHT / 2.0,
+            obstacle_opening_bottom_y_pos + opening_height / 2.0,
         ),
+        opening_height,
     );
 }
 
-fn random_opening_position_bottom_y(global_rng: &mut ResMut<GlobalRng>) -> f32 {
-    map_range(
-        global_rng.f32(),
-        0.0,
-        1.0,
-        -GAME_HEIGHT / 2.0 + OBSTACLE_OPENING_MIN_BOTTOM_Y_OFFSET,
-        GAME_HEIGHT / 2.0 - OBSTACLE_OPENING_MAX_TOP_Y_OFFSET - OBSTACLE_OPENING_HEIGHT,
-    )
+fn random_opening_position_bottom_y(
+    global_rng: &mut ResMut<GlobalRng>,
+    opening_height: f32,
+) -> f32 {
+    let (bottom, top) = opening_band();
+    map_range(global_rng.f32(), 0.0, 1.0, bottom, top - opening_height)
 }
 
 fn random_gap(global_rng: &mut ResMut<GlobalRng>) -> f32 {
@@ -160,44 +354,59 @@ fn map_range(value: f32, in_min: f32, in_max: f32, out_min: f32, out_max: f32) -
     (value - in_min) * (out_max - out_min) / (in_max - in_min) + out_min
 }
 
-fn spawn_obstacle_entity(
+/// Spawns a pipe, its `hitbox` already flipped along with the sprite.
+pub fn spawn_obstacle_entity(
     commands: &mut Commands,
     obstacle_image_handle: Handle<Image>,
+    hitbox: Hitbox,
     obstacle_pos: Vec2,
     flip_y: bool,
-) {
-    commands.spawn((
-        SpriteBundle {
-            texture: obstacle_image_handle.clone(),
-            sprite: Sprite {
-                flip_y,
-                anchor: Anchor::Center,
+) -> Entity {
+    commands
+        .spawn((
+            SpriteBundle {
+                texture: obstacle_image_handle.clone(),
+                sprite: Sprite {
+                    flip_y,
+                    anchor: Anchor::Center,
+                    ..Default::default()
+                },
+                transform: Transform::from_translation(Vec3::new(
+                    obstacle_pos.x,
+                    obstacle_pos.y,
+                    0.0,
+                )),
+                ..Default::default()
+            },
+            HorizontalMove { factor: 1.0 },
+            PhysicsBody::kinematic(hitbox)
+                .with_groups(COLLISION_GROUP_PIPE, COLLISION_GROUP_PLAYER),
+            Obstacle::GameOver,
+            DeathCause::Pipe,
+        ))
+        .id()
+}
+
+pub fn spawn_obstacle_opening_sensor_entity(
+    commands: &mut Commands,
+    obstacle_pos: Vec2,
+    opening_height: f32,
+) -> Entity {
+    commands
+        .spawn((
+            TransformBundle {
+                local: Transform::from_translation(Vec3::new(obstacle_pos.x, obstacle_pos.y, 0.0)),
                 ..Default::default()
             },
-            transform: Transform::from_translation(Vec3::new(obstacle_pos.x, obstacle_pos.y, 0.0)),
-            ..Default::default()
-        },
-        HorizontalMove { factor: 1.0 },
-        RigidBody::KinematicPositionBased,
-        Collider::cuboid(9.0, OBSTACLE_SPRITE_HEIGHT / 2.0),
-        GravityScale(0.0),
-        CollisionGroups::new(COLLISION_GROUP_GAME_OVER, COLLISION_GROUP_PLAYER),
-        Obstacle::GameOver,
-    ));
-}
-
-pub fn spawn_obstacle_opening_sensor_entity(commands: &mut Commands, obstacle_pos: Vec2) {
-    commands.spawn((
-        TransformBundle {
-            local: Transform::from_translation(Vec3::new(obstacle_pos.x, obstacle_pos.y, 0.0)),
-            ..Default::default()
-        },
-        HorizontalMove { factor: 1.0 },
-        Collider::cuboid(9.0, OBSTACLE_OPENING_HEIGHT / 2.0),
-        GravityScale(0.0),
-        CollisionGroups::new(COLLISION_GROUP_OPENING, COLLISION_GROUP_PLAYER),
-        Sensor,
-        Name::new("Opening"),
-        Obstacle::Opening,
-    ));
+            HorizontalMove { factor: 1.0 },
+            PhysicsBody::kinematic(Hitbox::Rect {
+                half_extents: Vec2::new(OBSTACLE_SPRITE_WIDTH / 2.0, opening_height / 2.0),
+            })
+            .sensor()
+            .with_groups(COLLISION_GROUP_OPENING, COLLISION_GROUP_PLAYER),
+            Name::new("Opening"),
+            Obstacle::Opening,
+            OpeningCrossing::default(),
+        ))
+        .id()
 }
@@ -0,0 +1,410 @@
+use std::collections::HashMap;
+
+use bevy::{
+    prelude::{
+        warn, App, Color, Commands, Component, DespawnRecursiveExt, Entity, EventReader,
+        GlobalTransform, Parent, Plugin, Query, Res, ResMut, Resource, Transform, Vec2, Visibility,
+        With, Without,
+    },
+    sprite::{Sprite, SpriteBundle},
+};
+use iyes_loopless::prelude::{AppLooplessStateExt, ConditionSet};
+use serde::Deserialize;
+
+use crate::{
+    app_states::{AppState, InGameState},
+    events::{DeathCause, GameEvent},
+    game::{GameTime, HorizontalMove},
+    schedule::GameStage,
+    transitions::no_screen_transition,
+};
+
+const PARTICLES_DATA: &str = include_str!("../assets/sprites/particles.ron");
+
+const PARTICLE_POOL_SIZE: usize = 256;
+const PARTICLES_Z: f32 = 5.0;
+
+const EFFECT_FEATHERS: &str = "feathers";
+const EFFECT_DUST: &str = "dust";
+const EFFECT_SPARKLES: &str = "sparkles";
+const EFFECT_DEBRIS: &str = "debris";
+// the player sprite is about 24 pixels high
+const DUST_OFFSET: Vec2 = Vec2::new(0.0, -10.0);
+
+/// Pooled sprite particles, emitted by [`ParticleEmitter`]s with the effects of
+/// `sprites/particles.ron`. Flaps, points and hits of the player emit their own bursts.
+pub struct ParticlesPlugin;
+
+impl Plugin for ParticlesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ParticleEffects>()
+            .init_resource::<ParticlePool>()
+            .init_resource::<ParticleRng>()
+            .add_enter_system(AppState::InGame(InGameState::ReadyToStart), clear_particles)
+            .add_system_set_to_stage(
+                GameStage::Ui,
+                ConditionSet::new()
+                    .run_in_state(AppState::InGame(InGameState::Playing))
+                    .run_if(no_screen_transition)
+                    .with_system(emit_game_event_particles)
+                    .into(),
+            )
+            .add_system_to_stage(GameStage::Ui, emit_particles)
+            .add_system_to_stage(GameStage::Ui, update_particles);
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ParticleEffectData {
+    count: (u32, u32),
+    lifetime: (f32, f32),
+    speed: (f32, f32),
+    direction: f32,
+    spread: f32,
+    gravity: f32,
+    drag: f32,
+    size: (f32, f32),
+    colors: (String, String),
+    scroll: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct ParticleEffect {
+    /// Particles emitted by a burst.
+    pub count: (u32, u32),
+    pub lifetime: (f32, f32),
+    pub speed: (f32, f32),
+    /// Radians, 0 pointing right.
+    pub direction: f32,
+    pub spread: f32,
+    pub gravity: f32,
+    pub drag: f32,
+    pub size: (f32, f32),
+    pub colors: (Color, Color),
+    /// Particles move along with the world.
+    pub scroll: bool,
+}
+
+impl From<ParticleEffectData> for ParticleEffect {
+    fn from(data: ParticleEffectData) -> Self {
+        let color = |hex: &str| Color::hex(hex).expect("a particle color is not a valid hex color");
+        Self {
+            count: data.count,
+            lifetime: data.lifetime,
+            speed: data.speed,
+            direction: data.direction.to_radians(),
+            spread: data.spread.to_radians(),
+            gravity: data.gravity,
+            drag: data.drag,
+            size: data.size,
+            colors: (color(&data.colors.0), color(&data.colors.1)),
+            scroll: data.scroll,
+        }
+    }
+}
+
+/// Particle effects by name, defined in `sprites/particles.ron`.
+#[derive(Resource, Debug, Clone)]
+pub struct ParticleEffects(HashMap<String, ParticleEffect>);
+
+impl Default for ParticleEffects {
+    fn default() -> Self {
+        let data: HashMap<String, ParticleEffectData> =
+            ron::from_str(PARTICLES_DATA).expect("the particles data file is invalid");
+        Self(
+            data.into_iter()
+                .map(|(name, effect)| (name, effect.into()))
+                .collect(),
+        )
+    }
+}
+
+impl ParticleEffects {
+    pub fn get(&self, name: &str) -> Option<&ParticleEffect> {
+        self.0.get(name)
+    }
+}
+
+/// Emits particles of an effect where its entity is.
+#[derive(Component, Debug, Clone)]
+pub struct ParticleEmitter {
+    pub effect: String,
+    /// Particles per second, after the burst.
+    pub rate: f32,
+    /// Seconds before the emitter is removed, `None` to emit until removed.
+    pub duration: Option<f32>,
+    burst_pending: bool,
+    accumulator: f32,
+}
+
+impl ParticleEmitter {
+    /// Emits a single burst, then despawns.
+    pub fn burst(effect: &str) -> Self {
+        Self {
+            effect: effect.to_string(),
+            rate: 0.0,
+            duration: Some(0.0),
+            burst_pending: true,
+            accumulator: 0.0,
+        }
+    }
+
+    pub fn continuous(effect: &str, rate: f32) -> Self {
+        Self {
+            effect: effect.to_string(),
+            rate,
+            duration: None,
+            burst_pending: false,
+            accumulator: 0.0,
+        }
+    }
+}
+
+#[derive(Component, Debug, Clone)]
+pub struct Particle {
+    alive: bool,
+    age: f32,
+    lifetime: f32,
+    velocity: Vec2,
+    gravity: f32,
+    drag: f32,
+    size: (f32, f32),
+    colors: (Color, Color),
+}
+
+/// Particle entities, spawned up to [`PARTICLE_POOL_SIZE`] and hidden between uses.
+#[derive(Resource, Debug, Default)]
+pub struct ParticlePool {
+    free: Vec<Entity>,
+    spawned: usize,
+}
+
+/// Randomness of the particles, kept apart from the global rng so that seeded runs play the same
+/// obstacles whatever the effects.
+#[derive(Resource, Debug)]
+pub struct ParticleRng(u32);
+
+impl Default for ParticleRng {
+    fn default() -> Self {
+        Self(0x9e37_79b9)
+    }
+}
+
+impl ParticleRng {
+    /// A number in `[0, 1)`, from a xorshift.
+    fn f32(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        (self.0 >> 8) as f32 / (1 << 24) as f32
+    }
+
+    fn range(&mut self, (min, max): (f32, f32)) -> f32 {
+        min + self.f32() * (max - min)
+    }
+}
+
+pub fn spawn_particle_burst(commands: &mut Commands, effect: &str, position: Vec2) {
+    let transform = Transform::from_translation(position.extend(PARTICLES_Z));
+    commands.spawn((
+        transform,
+        GlobalTransform::from(transform),
+        ParticleEmitter::burst(effect),
+    ));
+}
+
+pub fn emit_game_event_particles(mut commands: Commands, mut ev_game: EventReader<GameEvent>) {
+    for ev in ev_game.iter() {
+        match *ev {
+            GameEvent::PlayerFlapped { position, .. } => {
+                spawn_particle_burst(&mut commands, EFFECT_FEATHERS, position);
+            }
+            GameEvent::PlayerPassedAnOpening {
+                opening_position, ..
+            } => {
+                spawn_particle_burst(&mut commands, EFFECT_SPARKLES, opening_position);
+            }
+            GameEvent::PlayerHitGameOverCollider {
+                cause: DeathCause::Ground,
+                position,
+                ..
+            } => {
+                spawn_particle_burst(&mut commands, EFFECT_DUST, position + DUST_OFFSET);
+            }
+            GameEvent::PlayerHitGameOverCollider {
+                cause: DeathCause::Bird,
+                position,
+                ..
+            } => {
+                spawn_particle_burst(&mut commands, EFFECT_FEATHERS, position);
+            }
+            GameEvent::PlayerHitGameOverCollider {
+                cause: DeathCause::Pipe | DeathCause::Rock,
+                position,
+                ..
+            } => {
+                spawn_particle_burst(&mut commands, EFFECT_DEBRIS, position);
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn emit_particles(
+    mut commands: Commands,
+    mut q_emitters: Query<(
+        Entity,
+        &mut ParticleEmitter,
+        &GlobalTransform,
+        Option<&Parent>,
+    )>,
+    mut q_particles: Query<(&mut Particle, &mut Transform, &mut Sprite, &mut Visibility)>,
+    mut pool: ResMut<ParticlePool>,
+    mut rng: ResMut<ParticleRng>,
+    effects: Res<ParticleEffects>,
+    game_time: Res<GameTime>,
+) {
+    let dt = game_time.delta_seconds();
+    for (e, mut emitter, gt, parent) in q_emitters.iter_mut() {
+        let effect = match effects.get(&emitter.effect) {
+            Some(effect) => effect,
+            None => {
+                warn!("Unknown particle effect {}", emitter.effect);
+                commands.entity(e).remove::<ParticleEmitter>();
+                continue;
+            }
+        };
+
+        let mut count = 0;
+        if std::mem::take(&mut emitter.burst_pending) {
+            let (min, max) = effect.count;
+            count += rng.range((min as f32, max as f32 + 1.0)).floor() as u32;
+        }
+        emitter.accumulator += emitter.rate * dt;
+        count += emitter.accumulator.floor() as u32;
+        emitter.accumulator = emitter.accumulator.fract();
+
+        let origin = gt.translation().truncate();
+        for _ in 0..count {
+            let angle = effect.direction + (rng.f32() - 0.5) * effect.spread;
+            let particle = Particle {
+                alive: true,
+                age: 0.0,
+                lifetime: rng.range(effect.lifetime),
+                velocity: Vec2::new(angle.cos(), angle.sin()) * rng.range(effect.speed),
+                gravity: effect.gravity,
+                drag: effect.drag,
+                size: effect.size,
+                colors: effect.colors,
+            };
+            let translation = origin.extend(PARTICLES_Z);
+            let entity = if let Some(entity) = pool.free.pop() {
+                if let Ok((mut p, mut t, mut sprite, mut visibility)) = q_particles.get_mut(entity)
+                {
+                    *p = particle;
+                    t.translation = translation;
+                    sprite.color = effect.colors.0;
+                    sprite.custom_size = Some(Vec2::splat(effect.size.0));
+                    visibility.is_visible = true;
+                }
+                entity
+            } else if pool.spawned < PARTICLE_POOL_SIZE {
+                pool.spawned += 1;
+                commands
+                    .spawn((
+                        SpriteBundle {
+                            sprite: Sprite {
+                                color: effect.colors.0,
+                                custom_size: Some(Vec2::splat(effect.size.0)),
+                                ..Default::default()
+                            },
+                            transform: Transform::from_translation(translation),
+                            ..Default::default()
+                        },
+                        particle,
+                    ))
+                    .id()
+            } else {
+                // every particle is in use, the effect is cut short
+                break;
+            };
+            if effect.scroll {
+                commands
+                    .entity(entity)
+                    .insert(HorizontalMove { factor: 1.0 });
+            } else {
+                commands.entity(entity).remove::<HorizontalMove>();
+            }
+        }
+
+        if let Some(duration) = emitter.duration.as_mut() {
+            *duration -= dt;
+            if *duration <= 0.0 {
+                // standalone emitters are despawned, the others only stop emitting
+                if parent.is_some() {
+                    commands.entity(e).remove::<ParticleEmitter>();
+                } else {
+                    commands.entity(e).despawn_recursive();
+                }
+            }
+        }
+    }
+}
+
+pub fn update_particles(
+    mut q_particles: Query<(
+        Entity,
+        &mut Particle,
+        &mut Transform,
+        &mut Sprite,
+        &mut Visibility,
+    )>,
+    mut pool: ResMut<ParticlePool>,
+    game_time: Res<GameTime>,
+) {
+    let dt = game_time.delta_seconds();
+    for (e, mut p, mut t, mut sprite, mut visibility) in q_particles.iter_mut() {
+        if !p.alive {
+            continue;
+        }
+        p.age += dt;
+        if p.age >= p.lifetime {
+            p.alive = false;
+            visibility.is_visible = false;
+            pool.free.push(e);
+            continue;
+        }
+        let velocity = p.velocity * (1.0 - p.drag * dt).max(0.0) + Vec2::new(0.0, p.gravity * dt);
+        p.velocity = velocity;
+        t.translation += (velocity * dt).extend(0.0);
+
+        let progress = p.age / p.lifetime;
+        let size = p.size.0 + (p.size.1 - p.size.0) * progress;
+        sprite.custom_size = Some(Vec2::splat(size.max(0.0)));
+        let (start, end) = p.colors;
+        sprite.color = Color::rgba(
+            start.r() + (end.r() - start.r()) * progress,
+            start.g() + (end.g() - start.g()) * progress,
+            start.b() + (end.b() - start.b()) * progress,
+            start.a() + (end.a() - start.a()) * progress,
+        );
+    }
+}
+
+pub fn clear_particles(
+    mut commands: Commands,
+    q_emitters: Query<Entity, (With<ParticleEmitter>, Without<Parent>)>,
+    mut q_particles: Query<(Entity, &mut Particle, &mut Visibility)>,
+    mut pool: ResMut<ParticlePool>,
+) {
+    for e in q_emitters.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+    for (e, mut p, mut visibility) in q_particles.iter_mut() {
+        if p.alive {
+            p.alive = false;
+            visibility.is_visible = false;
+            pool.free.push(e);
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use bevy::prelude::{warn, Resource};
+use serde::{de::DeserializeOwned, Serialize};
+
+const SAVE_ROOT: &str = "saves";
+const DEFAULT_PROFILE_NAME: &str = "default";
+
+/// The player profile every persisted entry is scoped to.
+#[derive(Resource, Debug, Clone)]
+pub struct Profile {
+    pub name: String,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            name: DEFAULT_PROFILE_NAME.to_string(),
+        }
+    }
+}
+
+impl Profile {
+    fn entry_key(&self, key: &str) -> String {
+        format!("{}/{}/{}", SAVE_ROOT, self.name, key)
+    }
+}
+
+pub fn load<T: DeserializeOwned>(profile: &Profile, key: &str) -> Option<T> {
+    let entry_key = profile.entry_key(key);
+    let content = backend::read(&entry_key)?;
+    match ron::from_str(&content) {
+        Ok(value) => Some(value),
+        Err(err) => {
+            warn!("Unable to parse saved entry {}: {}", entry_key, err);
+            None
+        }
+    }
+}
+
+pub fn load_or_default<T: DeserializeOwned + Default>(profile: &Profile, key: &str) -> T {
+    load(profile, key).unwrap_or_default()
+}
+
+pub fn save<T: Serialize>(profile: &Profile, key: &str, value: &T) {
+    let entry_key = profile.entry_key(key);
+    match ron::to_string(value) {
+        Ok(content) => backend::write(&entry_key, &content),
+        Err(err) => warn!("Unable to serialize entry {}: {}", entry_key, err),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod backend {
+    use std::{fs, path::PathBuf};
+
+    use bevy::prelude::warn;
+
+    fn entry_path(entry_key: &str) -> PathBuf {
+        PathBuf::from(format!("{}.ron", entry_key))
+    }
+
+    pub fn read(entry_key: &str) -> Option<String> {
+        fs::read_to_string(entry_path(entry_key)).ok()
+    }
+
+    pub fn write(entry_key: &str, content: &str) {
+        let path = entry_path(entry_key);
+        if let Some(parent) = path.parent() {
+            if let Err(err) = fs::create_dir_all(parent) {
+                warn!("Unable to create save directory {:?}: {}", parent, err);
+                return;
+            }
+        }
+        if let Err(err) = fs::write(&path, content) {
+            warn!("Unable to write save file {:?}: {}", path, err);
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod backend {
+    use bevy::prelude::warn;
+    use web_sys::Storage;
+
+    fn local_storage() -> Option<Storage> {
+        web_sys::window().and_then(|w| w.local_storage().ok().flatten())
+    }
+
+    pub fn read(entry_key: &str) -> Option<String> {
+        local_storage()?.get_item(entry_key).ok().flatten()
+    }
+
+    pub fn write(entry_key: &str, content: &str) {
+        match local_storage() {
+            Some(storage) => {
+                if storage.set_item(entry_key, content).is_err() {
+                    warn!("Unable to write {} to the local storage", entry_key);
+                }
+            }
+            None => warn!(
+                "Local storage is not available, {} was not saved",
+                entry_key
+            ),
+        }
+    }
+}
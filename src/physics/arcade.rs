@@ -0,0 +1,239 @@
+use std::collections::BTreeSet;
+
+use bevy::prelude::{
+    App, Entity, EventWriter, Plugin, Query, Res, ResMut, Resource, Transform, Vec2,
+};
+
+use crate::{game::GameTime, schedule::GameStage};
+
+use super::{BodyKind, ContactEvent, Gravity, GravityScale, Hitbox, PhysicsBody, Velocity};
+
+// same upper bound as the steps of rapier, so both backends step alike
+const MAX_STEP_SECONDS: f32 = 1.0 / 60.0;
+// bodies pushed apart are left touching, this keeps their contact alive
+const CONTACT_MARGIN: f32 = 0.01;
+
+/// A small deterministic backend: gravity integration, convex shape overlaps, and dynamic
+/// bodies pushed out of the solid bodies they run into.
+pub struct PhysicsPlugin;
+
+impl Plugin for PhysicsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ContactEvent>()
+            .init_resource::<ArcadeContacts>()
+            .add_system_to_stage(GameStage::Physics, step_arcade_physics);
+    }
+}
+
+/// Pairs of bodies in contact after the last step, smallest entity first.
+#[derive(Resource, Default)]
+struct ArcadeContacts(BTreeSet<(Entity, Entity)>);
+
+fn step_arcade_physics(
+    mut q_bodies: Query<(
+        Entity,
+        &PhysicsBody,
+        &mut Transform,
+        Option<&mut Velocity>,
+        Option<&GravityScale>,
+    )>,
+    mut contacts: ResMut<ArcadeContacts>,
+    mut ev_contacts: EventWriter<ContactEvent>,
+    gravity: Res<Gravity>,
+    game_time: Res<GameTime>,
+) {
+    let dt = game_time.delta_seconds().min(MAX_STEP_SECONDS);
+    // a stopped clock stops the simulation, like rapier's inactive pipeline
+    if dt == 0.0 {
+        return;
+    }
+
+    // semi-implicit euler, like rapier
+    for (_, body, mut t, v, gravity_scale) in q_bodies.iter_mut() {
+        if let (BodyKind::Dynamic, Some(mut v)) = (body.kind, v) {
+            v.0.y += gravity.0.y * gravity_scale.map_or(1.0, |g| g.0) * dt;
+            t.translation.y += v.0.y * dt;
+        }
+    }
+
+    let bodies: Vec<(Entity, PhysicsBody, Vec2)> = q_bodies
+        .iter()
+        .map(|(e, body, t, _, _)| (e, body.clone(), t.translation.truncate()))
+        .collect();
+
+    let mut touching = BTreeSet::new();
+    for (i, (ent_a, body_a, pos_a)) in bodies.iter().enumerate() {
+        for (ent_b, body_b, pos_b) in bodies.iter().skip(i + 1) {
+            if !body_a.interacts_with(body_b) {
+                continue;
+            }
+            if let Some(push) = penetration(&body_a.hitbox, *pos_a, &body_b.hitbox, *pos_b) {
+                touching.insert((*ent_a.min(ent_b), *ent_a.max(ent_b)));
+                if !body_a.sensor && !body_b.sensor {
+                    if body_a.kind == BodyKind::Dynamic {
+                        push_out(&mut q_bodies, *ent_a, push);
+                    } else {
+                        push_out(&mut q_bodies, *ent_b, -push);
+                    }
+                }
+            }
+        }
+    }
+
+    for &(ent_a, ent_b) in touching.difference(&contacts.0) {
+        ev_contacts.send(ContactEvent::Started(ent_a, ent_b));
+    }
+    for &(ent_a, ent_b) in contacts.0.difference(&touching) {
+        if q_bodies.contains(ent_a) && q_bodies.contains(ent_b) {
+            ev_contacts.send(ContactEvent::Stopped(ent_a, ent_b));
+        }
+    }
+    contacts.0 = touching;
+}
+
+/// Moves a dynamic body vertically out of a solid one, its horizontal position being locked.
+fn push_out(
+    q_bodies: &mut Query<(
+        Entity,
+        &PhysicsBody,
+        &mut Transform,
+        Option<&mut Velocity>,
+        Option<&GravityScale>,
+    )>,
+    entity: Entity,
+    push: Vec2,
+) {
+    if let Ok((_, _, mut t, v, _)) = q_bodies.get_mut(entity) {
+        t.translation.y += push.y;
+        if let Some(mut v) = v {
+            if v.0.y * push.y < 0.0 {
+                v.0.y = 0.0;
+            }
+        }
+    }
+}
+
+/// Smallest translation moving the shape `a` out of the shape `b`, `None` when they are apart.
+/// Touching shapes overlap by a zero length translation. Compound shapes are pushed out of their
+/// deepest overlap.
+fn penetration(a: &Hitbox, a_pos: Vec2, b: &Hitbox, b_pos: Vec2) -> Option<Vec2> {
+    let (mut a_parts, mut b_parts) = (Vec::new(), Vec::new());
+    convex_parts(a, a_pos, &mut a_parts);
+    convex_parts(b, b_pos, &mut b_parts);
+    a_parts
+        .iter()
+        .flat_map(|a| b_parts.iter().filter_map(|b| convex_penetration(a, b)))
+        .reduce(|deepest, push| {
+            if push.length_squared() > deepest.length_squared() {
+                push
+            } else {
+                deepest
+            }
+        })
+}
+
+/// A convex shape in world space, rounded by `radius`: a circle is a single rounded point.
+struct Convex {
+    points: Vec<Vec2>,
+    radius: f32,
+}
+
+fn convex_parts(hitbox: &Hitbox, pos: Vec2, parts: &mut Vec<Convex>) {
+    match hitbox {
+        Hitbox::Circle { radius } => parts.push(Convex {
+            points: vec![pos],
+            radius: *radius,
+        }),
+        Hitbox::Rect { half_extents } => parts.push(Convex {
+            points: [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]
+                .iter()
+                .map(|&(x, y)| pos + Vec2::new(x, y) * *half_extents)
+                .collect(),
+            radius: 0.0,
+        }),
+        Hitbox::Polygon { points } => parts.push(Convex {
+            points: points.iter().map(|p| pos + *p).collect(),
+            radius: 0.0,
+        }),
+        Hitbox::Compound { parts: compound } => {
+            for (offset, part) in compound {
+                convex_parts(part, pos + *offset, parts);
+            }
+        }
+    }
+}
+
+/// Separating axis test: the shapes overlap when their projections overlap on the normals of
+/// all their edges, and on the axis from a rounded point to the closest point of the other shape.
+fn convex_penetration(a: &Convex, b: &Convex) -> Option<Vec2> {
+    let mut axes: Vec<Vec2> = edge_normals(a).chain(edge_normals(b)).collect();
+    axes.extend(point_axis(a, b));
+    axes.extend(point_axis(b, a));
+    if axes.is_empty() {
+        // two circles sharing their center
+        return Some(Vec2::ZERO);
+    }
+
+    let mut push: Option<Vec2> = None;
+    for axis in axes {
+        let (a_min, a_max) = project(a, axis);
+        let (b_min, b_max) = project(b, axis);
+        let (depth_backward, depth_forward) = (a_max - b_min, b_max - a_min);
+        let depth = depth_backward.min(depth_forward);
+        if depth <= -CONTACT_MARGIN {
+            return None;
+        }
+        let axis_push = if depth_backward < depth_forward {
+            -axis * depth.max(0.0)
+        } else {
+            axis * depth.max(0.0)
+        };
+        if push.map_or(true, |p| axis_push.length() < p.length()) {
+            push = Some(axis_push);
+        }
+    }
+    push
+}
+
+fn edge_normals(shape: &Convex) -> impl Iterator<Item = Vec2> + '_ {
+    let count = if shape.points.len() > 1 {
+        shape.points.len()
+    } else {
+        0
+    };
+    (0..count).map(move |i| {
+        (shape.points[(i + 1) % count] - shape.points[i])
+            .perp()
+            .normalize_or_zero()
+    })
+}
+
+fn point_axis(point: &Convex, other: &Convex) -> Option<Vec2> {
+    if point.points.len() != 1 {
+        return None;
+    }
+    let center = point.points[0];
+    other
+        .points
+        .iter()
+        .map(|p| *p - center)
+        .reduce(|closest, d| {
+            if d.length_squared() < closest.length_squared() {
+                d
+            } else {
+                closest
+            }
+        })
+        .and_then(|d| d.try_normalize())
+}
+
+fn project(shape: &Convex, axis: Vec2) -> (f32, f32) {
+    let (min, max) = shape
+        .points
+        .iter()
+        .map(|p| p.dot(axis))
+        .fold((f32::MAX, f32::MIN), |(min, max), d| {
+            (min.min(d), max.max(d))
+        });
+    (min - shape.radius, max + shape.radius)
+}
@@ -0,0 +1,499 @@
+use bevy::prelude::{Component, Entity, Resource, Vec2};
+
+#[cfg(all(feature = "rapier", feature = "arcade-physics"))]
+compile_error!("only one of the `rapier` and `arcade-physics` physics backends can be enabled");
+#[cfg(not(any(feature = "rapier", feature = "arcade-physics")))]
+compile_error!(
+    "a physics backend has to be selected with the `rapier` or `arcade-physics` feature"
+);
+
+#[cfg(feature = "arcade-physics")]
+mod arcade;
+#[cfg(feature = "rapier")]
+mod rapier;
+
+#[cfg(feature = "arcade-physics")]
+pub use arcade::PhysicsPlugin;
+#[cfg(feature = "rapier")]
+pub use rapier::PhysicsPlugin;
+
+/// World gravity, in pixels per second squared.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct Gravity(pub Vec2);
+
+/// Linear velocity of a dynamic body, in pixels per second.
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq)]
+pub struct Velocity(pub Vec2);
+
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct GravityScale(pub f32);
+
+/// Shape of a body, centered on its transform. Bodies never rotate, so shapes are only ever
+/// translated.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Hitbox {
+    Circle {
+        radius: f32,
+    },
+    Rect {
+        half_extents: Vec2,
+    },
+    /// A convex polygon, its points listed in order around it.
+    Polygon {
+        points: Vec<Vec2>,
+    },
+    /// Several shapes, each one offset from the body center.
+    Compound {
+        parts: Vec<(Vec2, Hitbox)>,
+    },
+}
+
+impl Hitbox {
+    /// The same shape with its edges moved inwards by `margin`.
+    pub fn shrunk(&self, margin: f32) -> Hitbox {
+        match self {
+            Hitbox::Circle { radius } => Hitbox::Circle {
+                radius: (radius - margin).max(0.0),
+            },
+            Hitbox::Rect { half_extents } => Hitbox::Rect {
+                half_extents: (*half_extents - Vec2::splat(margin)).max(Vec2::ZERO),
+            },
+            Hitbox::Polygon { points } => Hitbox::Polygon {
+                points: inset_polygon(points, margin),
+            },
+            Hitbox::Compound { parts } => Hitbox::Compound {
+                parts: parts
+                    .iter()
+                    .map(|(offset, part)| (*offset, part.shrunk(margin)))
+                    .collect(),
+            },
+        }
+    }
+
+    /// The same shape scaled around the body center.
+    pub fn scaled(&self, factor: f32) -> Hitbox {
+        match self {
+            Hitbox::Circle { radius } => Hitbox::Circle {
+                radius: radius * factor,
+            },
+            Hitbox::Rect { half_extents } => Hitbox::Rect {
+                half_extents: *half_extents * factor,
+            },
+            Hitbox::Polygon { points } => Hitbox::Polygon {
+                points: points.iter().map(|p| *p * factor).collect(),
+            },
+            Hitbox::Compound { parts } => Hitbox::Compound {
+                parts: parts
+                    .iter()
+                    .map(|(offset, part)| (*offset * factor, part.scaled(factor)))
+                    .collect(),
+            },
+        }
+    }
+
+    /// The shape mirrored upside down, for vertically flipped sprites.
+    pub fn flipped_y(&self) -> Hitbox {
+        let flip = |p: &Vec2| Vec2::new(p.x, -p.y);
+        match self {
+            Hitbox::Circle { .. } | Hitbox::Rect { .. } => self.clone(),
+            // reversed to keep the points winding the same way
+            Hitbox::Polygon { points } => Hitbox::Polygon {
+                points: points.iter().rev().map(flip).collect(),
+            },
+            Hitbox::Compound { parts } => Hitbox::Compound {
+                parts: parts
+                    .iter()
+                    .map(|(offset, part)| (flip(offset), part.flipped_y()))
+                    .collect(),
+            },
+        }
+    }
+
+    /// Bottom left and top right corners of the box bounding the shape.
+    pub fn aabb(&self) -> (Vec2, Vec2) {
+        match self {
+            Hitbox::Circle { radius } => (-Vec2::splat(*radius), Vec2::splat(*radius)),
+            Hitbox::Rect { half_extents } => (-*half_extents, *half_extents),
+            Hitbox::Polygon { points } => points.iter().fold(
+                (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+                |(min, max), p| (min.min(*p), max.max(*p)),
+            ),
+            Hitbox::Compound { parts } => parts.iter().fold(
+                (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+                |(min, max), (offset, part)| {
+                    let (part_min, part_max) = part.aabb();
+                    (min.min(*offset + part_min), max.max(*offset + part_max))
+                },
+            ),
+        }
+    }
+}
+
+/// Moves every edge of a convex polygon inwards, whichever way its points wind.
+fn inset_polygon(points: &[Vec2], margin: f32) -> Vec<Vec2> {
+    let count = points.len();
+    let winding = (0..count)
+        .map(|i| points[i].perp_dot(points[(i + 1) % count]))
+        .sum::<f32>()
+        .signum();
+    let outward_normal = |from: Vec2, to: Vec2| (to - from).perp().normalize_or_zero() * -winding;
+    (0..count)
+        .map(|i| {
+            let prev = points[(i + count - 1) % count];
+            let next = points[(i + 1) % count];
+            let n1 = outward_normal(prev, points[i]);
+            let n2 = outward_normal(points[i], next);
+            points[i] - (n1 + n2) * margin / (1.0 + n1.dot(n2))
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyKind {
+    /// Falls with the gravity and moves with its [`Velocity`]. Only its vertical position is
+    /// simulated, the world scrolls around it.
+    Dynamic,
+    /// Moved by the game through its transform.
+    Kinematic,
+    Fixed,
+}
+
+/// Describes how an entity takes part in the physics, whatever the backend simulating it.
+///
+/// Two bodies only interact when each one is a member of a group the other one filters, and at
+/// least one of them is dynamic.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct PhysicsBody {
+    pub kind: BodyKind,
+    pub hitbox: Hitbox,
+    /// Sensors report contacts but never push other bodies.
+    pub sensor: bool,
+    pub memberships: u32,
+    pub filters: u32,
+}
+
+impl PhysicsBody {
+    pub fn new(kind: BodyKind, hitbox: Hitbox) -> Self {
+        Self {
+            kind,
+            hitbox,
+            sensor: false,
+            memberships: u32::MAX,
+            filters: u32::MAX,
+        }
+    }
+
+    pub fn dynamic(hitbox: Hitbox) -> Self {
+        Self::new(BodyKind::Dynamic, hitbox)
+    }
+
+    pub fn kinematic(hitbox: Hitbox) -> Self {
+        Self::new(BodyKind::Kinematic, hitbox)
+    }
+
+    pub fn fixed(hitbox: Hitbox) -> Self {
+        Self::new(BodyKind::Fixed, hitbox)
+    }
+
+    pub fn sensor(mut self) -> Self {
+        self.sensor = true;
+        self
+    }
+
+    pub fn with_groups(mut self, memberships: u32, filters: u32) -> Self {
+        self.memberships = memberships;
+        self.filters = filters;
+        self
+    }
+
+    pub fn interacts_with(&self, other: &PhysicsBody) -> bool {
+        self.memberships & other.filters != 0
+            && other.memberships & self.filters != 0
+            && (self.kind == BodyKind::Dynamic || other.kind == BodyKind::Dynamic)
+    }
+}
+
+/// Start and end of a contact between two bodies, as reported by the physics backend. Contacts
+/// with a despawned body end silently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContactEvent {
+    Started(Entity, Entity),
+    Stopped(Entity, Entity),
+}
+
+/// Scripted scenarios stepped on the backend the crate is built with, which is checked against
+/// the same expected heights and contact frames whatever it is. The tests run once per backend:
+/// `cargo test` and `cargo test --no-default-features --features arcade-physics`.
+#[cfg(test)]
+pub mod tests {
+    use bevy::{
+        ecs::event::{Events, ManualEventReader},
+        prelude::{App, CoreStage, GlobalTransform, Input, KeyCode, Transform},
+        time::Time,
+        transform::TransformPlugin,
+    };
+    #[cfg(feature = "rapier")]
+    use bevy_rapier2d::prelude::{NoUserData, RapierPhysicsPlugin};
+
+    use super::*;
+    #[cfg(feature = "rapier")]
+    use crate::consts::PIXELS_PER_METER;
+    use crate::{
+        consts::GRAVITY,
+        game::{update_game_time, GameTime, TimeScale},
+        schedule::GameSchedulePlugin,
+    };
+
+    const STEP_SECONDS: f32 = 1.0 / 60.0;
+    const PLAYER_RADIUS: f32 = 10.0;
+    const PIPE_SPEED: f32 = 3.0;
+    // rapier picks moved bodies up once their transform propagated, a frame later
+    const CONTACT_FRAME_TOLERANCE: u32 = 1;
+    const POSITION_TOLERANCE: f32 = 0.5;
+
+    /// A player at the origin among other bodies, stepped frame by frame.
+    pub struct Scenario {
+        app: App,
+        player: Entity,
+        contacts: ManualEventReader<ContactEvent>,
+        /// Contacts reported so far, with the frame reporting them.
+        log: Vec<(u32, ContactEvent)>,
+        frame: u32,
+    }
+
+    impl Scenario {
+        pub fn new(gravity_scale: f32) -> Self {
+            let mut app = App::new();
+            app.add_plugin(TransformPlugin)
+                .insert_resource(Time::default())
+                .init_resource::<Input<KeyCode>>()
+                .init_resource::<TimeScale>()
+                .init_resource::<GameTime>()
+                .insert_resource(Gravity(Vec2::new(0.0, GRAVITY)))
+                .add_system_to_stage(CoreStage::First, update_game_time);
+            #[cfg(feature = "rapier")]
+            app.add_plugin(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(
+                PIXELS_PER_METER,
+            ));
+            app.add_plugin(GameSchedulePlugin).add_plugin(PhysicsPlugin);
+            app.world
+                .resource_mut::<TimeScale>()
+                .set_frame_advance(true);
+            let player = app
+                .world
+                .spawn((
+                    PhysicsBody::dynamic(Hitbox::Circle {
+                        radius: PLAYER_RADIUS,
+                    }),
+                    Velocity::default(),
+                    GravityScale(gravity_scale),
+                    Transform::default(),
+                    GlobalTransform::default(),
+                ))
+                .id();
+            let contacts = app.world.resource::<Events<ContactEvent>>().get_reader();
+            Self {
+                app,
+                player,
+                contacts,
+                log: Vec::new(),
+                frame: 0,
+            }
+        }
+
+        pub fn spawn(&mut self, position: Vec2, body: PhysicsBody) -> Entity {
+            let transform = Transform::from_translation(position.extend(0.0));
+            self.app
+                .world
+                .spawn((body, transform, GlobalTransform::from(transform)))
+                .id()
+        }
+
+        /// Runs a frame with the clock stopped, for the backend to pick the bodies up.
+        pub fn start(&mut self) {
+            self.app.update();
+        }
+
+        /// Plays a frame of [`STEP_SECONDS`].
+        pub fn step(&mut self) {
+            self.app.world.resource_mut::<TimeScale>().request_step();
+            self.app.update();
+            self.frame += 1;
+            let events = self.app.world.resource::<Events<ContactEvent>>();
+            for contact in self.contacts.iter(events) {
+                self.log.push((self.frame, *contact));
+            }
+        }
+
+        fn set_position(&mut self, entity: Entity, position: Vec2) {
+            if let Some(mut t) = self.app.world.get_mut::<Transform>(entity) {
+                t.translation = position.extend(t.translation.z);
+            }
+        }
+
+        fn flap(&mut self, velocity: f32) {
+            if let Some(mut v) = self.app.world.get_mut::<Velocity>(self.player) {
+                v.0.y = velocity;
+            }
+        }
+
+        fn player_height(&self) -> f32 {
+            self.app
+                .world
+                .get::<Transform>(self.player)
+                .map_or(f32::NAN, |t| t.translation.y)
+        }
+
+        /// Checks the contacts reported are the expected ones between the player and a body,
+        /// started or not, around the expected frame. Failures are prefixed with `context`.
+        pub fn assert_contacts(&self, context: &str, expected: &[(bool, Entity, u32)]) {
+            assert_eq!(
+                self.log.len(),
+                expected.len(),
+                "{context}: contacts {:?}",
+                self.log
+            );
+            for (&(frame, contact), &(started, other, expected_frame)) in
+                self.log.iter().zip(expected)
+            {
+                let (is_started, a, b) = match contact {
+                    ContactEvent::Started(a, b) => (true, a, b),
+                    ContactEvent::Stopped(a, b) => (false, a, b),
+                };
+                assert_eq!(is_started, started, "{context}: contact {contact:?}");
+                assert!(
+                    [a, b].contains(&self.player) && [a, b].contains(&other),
+                    "{context}: contact {contact:?}"
+                );
+                assert!(
+                    frame.abs_diff(expected_frame) <= CONTACT_FRAME_TOLERANCE,
+                    "{context}: contact {contact:?} on frame {frame}, expected on frame \
+                     {expected_frame}"
+                );
+            }
+        }
+    }
+
+    /// Heights of a falling player by semi-implicit euler, its vertical velocity set by a flap
+    /// before the given frame.
+    fn expected_heights(frames: u32, flap: Option<(u32, f32)>) -> Vec<f32> {
+        let (mut y, mut v) = (0.0, 0.0);
+        (1..=frames)
+            .map(|frame| {
+                if let Some((flap_frame, flap_velocity)) = flap {
+                    if frame == flap_frame {
+                        v = flap_velocity;
+                    }
+                }
+                v += GRAVITY * STEP_SECONDS;
+                y += v * STEP_SECONDS;
+                y
+            })
+            .collect()
+    }
+
+    fn assert_heights(scenario: &mut Scenario, frames: u32, flap: Option<(u32, f32)>) {
+        for (frame, expected) in (1..=frames).zip(expected_heights(frames, flap)) {
+            if let Some((flap_frame, flap_velocity)) = flap {
+                if frame == flap_frame {
+                    scenario.flap(flap_velocity);
+                }
+            }
+            scenario.step();
+            let height = scenario.player_height();
+            assert!(
+                (height - expected).abs() <= POSITION_TOLERANCE,
+                "height {height} on frame {frame}, expected {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn falls_with_the_gravity() {
+        let mut scenario = Scenario::new(1.0);
+        scenario.start();
+        assert_heights(&mut scenario, 30, None);
+        scenario.assert_contacts("fall", &[]);
+    }
+
+    #[test]
+    fn flaps_up() {
+        let mut scenario = Scenario::new(1.0);
+        scenario.start();
+        assert_heights(&mut scenario, 40, Some((10, 360.0)));
+        scenario.assert_contacts("flap", &[]);
+    }
+
+    #[test]
+    fn a_stopped_clock_stops_the_simulation() {
+        let mut scenario = Scenario::new(1.0);
+        scenario.start();
+        scenario.app.update();
+        scenario.app.update();
+        assert_eq!(scenario.player_height(), 0.0);
+    }
+
+    #[test]
+    fn hits_a_pipe() {
+        let mut scenario = Scenario::new(0.0);
+        let start = Vec2::new(101.0, 0.0);
+        let pipe = scenario.spawn(
+            start,
+            PhysicsBody::kinematic(Hitbox::Rect {
+                half_extents: Vec2::new(9.0, 80.0),
+            }),
+        );
+        scenario.start();
+        for frame in 1..=32 {
+            scenario.set_position(pipe, start - Vec2::X * PIPE_SPEED * frame as f32);
+            scenario.step();
+        }
+        // the pipe reaches the player on frame 28, without pushing it up or down
+        scenario.assert_contacts("pipe hit", &[(true, pipe, 28)]);
+        assert!(scenario.player_height().abs() <= POSITION_TOLERANCE);
+    }
+
+    #[test]
+    fn crosses_an_opening() {
+        let mut scenario = Scenario::new(0.0);
+        let start = Vec2::new(60.0, 0.0);
+        let opening = scenario.spawn(
+            start,
+            PhysicsBody::kinematic(Hitbox::Rect {
+                half_extents: Vec2::new(9.0, 40.0),
+            })
+            .sensor(),
+        );
+        scenario.start();
+        for frame in 1..=35 {
+            scenario.set_position(opening, start - Vec2::X * PIPE_SPEED * frame as f32);
+            scenario.step();
+        }
+        // the opening overlaps the player from frame 14 to frame 26
+        scenario.assert_contacts("opening", &[(true, opening, 14), (false, opening, 27)]);
+        assert!(scenario.player_height().abs() <= POSITION_TOLERANCE);
+    }
+
+    #[test]
+    fn lands_on_the_ground() {
+        let mut scenario = Scenario::new(1.0);
+        let ground = scenario.spawn(
+            Vec2::new(0.0, -100.0),
+            PhysicsBody::fixed(Hitbox::Rect {
+                half_extents: Vec2::new(300.0, 10.0),
+            }),
+        );
+        scenario.start();
+        for _ in 0..60 {
+            scenario.step();
+        }
+        // the player falls 82.8 pixels in 23 frames, its bottom then sinking in the ground
+        scenario.assert_contacts("ground hit", &[(true, ground, 23)]);
+        let rest_height = -90.0 + PLAYER_RADIUS;
+        let height = scenario.player_height();
+        assert!(
+            (height - rest_height).abs() <= 1.0,
+            "height {height}, expected {rest_height}"
+        );
+    }
+}
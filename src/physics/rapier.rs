@@ -0,0 +1,162 @@
+use bevy::prelude::{
+    Added, App, ChangeTrackers, Changed, Commands, Entity, EventReader, EventWriter, Input,
+    KeyCode, Plugin, Query, Res, ResMut,
+};
+use bevy_rapier2d::prelude::{
+    ActiveEvents, Ccd, Collider, CollisionEvent, CollisionEventFlags, CollisionGroups,
+    DebugRenderContext, GravityScale as RapierGravityScale, Group, LockedAxes, RapierConfiguration,
+    RigidBody, Sensor, TimestepMode, Velocity as RapierVelocity,
+};
+
+use crate::{game::GameTime, schedule::GameStage};
+
+use super::{BodyKind, ContactEvent, Gravity, GravityScale, Hitbox, PhysicsBody, Velocity};
+
+// the upper bound of the default rapier variable timestep, also used by the arcade backend
+const MAX_STEP_SECONDS: f32 = 1.0 / 60.0;
+
+const HITBOX_DEBUG_RENDER_KEY: KeyCode = KeyCode::F2;
+
+/// Mirrors the [`PhysicsBody`]s into rapier, which has to be added beforehand, and reads the
+/// simulation results back.
+pub struct PhysicsPlugin;
+
+impl Plugin for PhysicsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ContactEvent>()
+            .add_system_to_stage(GameStage::Movement, attach_rapier_bodies)
+            .add_system_to_stage(GameStage::Movement, update_rapier_colliders)
+            .add_system_to_stage(GameStage::Movement, push_bodies_to_rapier)
+            .add_system_to_stage(GameStage::Physics, pull_bodies_from_rapier)
+            .add_system_to_stage(GameStage::Physics, forward_rapier_collision_events)
+            .add_system(toggle_hitbox_debug_render);
+    }
+}
+
+fn rapier_collider(hitbox: &Hitbox) -> Collider {
+    match hitbox {
+        Hitbox::Circle { radius } => Collider::ball(*radius),
+        Hitbox::Rect { half_extents } => Collider::cuboid(half_extents.x, half_extents.y),
+        Hitbox::Polygon { points } => Collider::convex_polyline(points.clone())
+            .expect("hitbox polygons need at least three points not on a line"),
+        Hitbox::Compound { parts } => Collider::compound(
+            parts
+                .iter()
+                .map(|(offset, part)| (*offset, 0.0, rapier_collider(part)))
+                .collect(),
+        ),
+    }
+}
+
+fn attach_rapier_bodies(
+    mut commands: Commands,
+    q_bodies: Query<(Entity, &PhysicsBody, Option<&GravityScale>), Added<PhysicsBody>>,
+) {
+    for (e, body, gravity_scale) in q_bodies.iter() {
+        let mut entity = commands.entity(e);
+        entity.insert((
+            rapier_collider(&body.hitbox),
+            CollisionGroups::new(
+                Group::from_bits_truncate(body.memberships),
+                Group::from_bits_truncate(body.filters),
+            ),
+        ));
+        if body.sensor {
+            entity.insert(Sensor);
+        }
+        match body.kind {
+            BodyKind::Dynamic => entity.insert((
+                RigidBody::Dynamic,
+                Ccd::enabled(),
+                RapierVelocity::zero(),
+                RapierGravityScale(gravity_scale.map_or(1.0, |g| g.0)),
+                LockedAxes::TRANSLATION_LOCKED_X | LockedAxes::ROTATION_LOCKED,
+                ActiveEvents::COLLISION_EVENTS,
+            )),
+            BodyKind::Kinematic => entity.insert(RigidBody::KinematicPositionBased),
+            BodyKind::Fixed => entity.insert(RigidBody::Fixed),
+        };
+    }
+}
+
+/// Rebuilds the collider of a body whose hitbox or groups changed after it was added.
+fn update_rapier_colliders(
+    mut commands: Commands,
+    q_bodies: Query<(Entity, &PhysicsBody, ChangeTrackers<PhysicsBody>), Changed<PhysicsBody>>,
+) {
+    for (e, body, trackers) in q_bodies.iter() {
+        if trackers.is_added() {
+            continue;
+        }
+        commands.entity(e).insert((
+            rapier_collider(&body.hitbox),
+            CollisionGroups::new(
+                Group::from_bits_truncate(body.memberships),
+                Group::from_bits_truncate(body.filters),
+            ),
+        ));
+    }
+}
+
+fn push_bodies_to_rapier(
+    mut q_bodies: Query<(
+        &Velocity,
+        Option<&GravityScale>,
+        &mut RapierVelocity,
+        &mut RapierGravityScale,
+    )>,
+    gravity: Res<Gravity>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+    game_time: Res<GameTime>,
+) {
+    if gravity.is_changed() {
+        rapier_config.gravity = gravity.0;
+    }
+    // rapier steps once a frame by the gameplay clock, whatever the real frame time
+    let dt = game_time.delta_seconds().min(MAX_STEP_SECONDS);
+    rapier_config.physics_pipeline_active = dt > 0.0;
+    if dt > 0.0 {
+        rapier_config.timestep_mode = TimestepMode::Fixed { dt, substeps: 1 };
+    }
+    for (v, gravity_scale, mut rapier_v, mut rapier_gravity_scale) in q_bodies.iter_mut() {
+        rapier_v.linvel = v.0;
+        rapier_gravity_scale.0 = gravity_scale.map_or(1.0, |g| g.0);
+    }
+}
+
+fn pull_bodies_from_rapier(mut q_bodies: Query<(&mut Velocity, &RapierVelocity)>) {
+    for (mut v, rapier_v) in q_bodies.iter_mut() {
+        v.0 = rapier_v.linvel;
+    }
+}
+
+fn forward_rapier_collision_events(
+    mut ev_collisions: EventReader<CollisionEvent>,
+    mut ev_contacts: EventWriter<ContactEvent>,
+) {
+    for col_ev in ev_collisions.iter() {
+        match col_ev {
+            CollisionEvent::Started(ent_a, ent_b, _) => {
+                ev_contacts.send(ContactEvent::Started(*ent_a, *ent_b))
+            }
+            // the contact ended because a collider was despawned
+            CollisionEvent::Stopped(_, _, flags)
+                if flags.contains(CollisionEventFlags::REMOVED) => {}
+            CollisionEvent::Stopped(ent_a, ent_b, _) => {
+                ev_contacts.send(ContactEvent::Stopped(*ent_a, *ent_b))
+            }
+        }
+    }
+}
+
+/// Shows or hides the hitboxes drawn by the rapier debug renderer, when it was added.
+fn toggle_hitbox_debug_render(
+    keyboard_input: Res<Input<KeyCode>>,
+    debug_render: Option<ResMut<DebugRenderContext>>,
+) {
+    if let Some(mut debug_render) = debug_render {
+        if keyboard_input.just_pressed(HITBOX_DEBUG_RENDER_KEY) {
+            debug_render.enabled = !debug_render.enabled;
+        }
+    }
+}
@@ -1,25 +1,146 @@
 use bevy::{
     prelude::{
-        Assets, Commands, Component, EventReader, Input, KeyCode, MouseButton, Quat, Query, Res,
-        ResMut, Resource, Transform, Vec2, Vec3, With,
+        App, BuildChildren, Commands, Component, DespawnRecursiveExt, Entity, EventReader,
+        EventWriter, Handle, Input, KeyCode, MouseButton, Plugin, Quat, Query, Res, ResMut,
+        Resource, SpatialBundle, Transform, Vec2, Vec3, With,
     },
     sprite::{SpriteSheetBundle, TextureAtlas, TextureAtlasSprite},
-    time::{Timer, TimerMode},
-};
-use bevy_rapier2d::prelude::{
-    ActiveEvents, Ccd, Collider, CollisionGroups, GravityScale, LockedAxes, RigidBody, Velocity,
 };
 
+use iyes_loopless::prelude::{AppLooplessStateExt, ConditionSet, IntoConditionalSystem};
+
+use crate::assets::GameAssets;
 use crate::{
-    animations::{AnimationIndices, AnimationTimer},
-    consts::{COLLISION_GROUP_GAME_OVER, COLLISION_GROUP_OPENING, COLLISION_GROUP_PLAYER},
+    animations::{Animator, CLIP_FLAP, CLIP_HURT, CLIP_IDLE},
+    app_states::{AppState, InGameState, MenuState},
+    characters::{
+        browse_characters_on_input, load_selected_character, start_character_select,
+        CharacterDefinition, CharacterRoster, SelectedCharacter,
+    },
+    console::{ConsoleCommand, ConsoleCommandInfo, ConsoleLine, RegisterConsoleCommands},
+    consts::{
+        COLLISION_GROUP_GAME_OVER, COLLISION_GROUP_HAZARD, COLLISION_GROUP_OPENING,
+        COLLISION_GROUP_PICKUP, COLLISION_GROUP_PIPE, COLLISION_GROUP_PLAYER,
+    },
     events::GameEvent,
+    game::GameTick,
+    hitboxes::HitboxDefinitions,
+    persistence::Profile,
+    physics::{Gravity, GravityScale, Hitbox, PhysicsBody, Velocity},
+    schedule::GameStage,
+    transitions::no_screen_transition,
 };
-use crate::{assets::GameAssets, consts::JUMP_IMPULSE_VALUE};
+
+const PLAYER_CONSOLE_COMMANDS: [ConsoleCommandInfo; 3] = [
+    ConsoleCommandInfo {
+        name: "god",
+        args: "",
+        help: "toggle the player invulnerability",
+        completions: &[],
+    },
+    ConsoleCommandInfo {
+        name: "score",
+        args: "<n>",
+        help: "set the score of the run",
+        completions: &[],
+    },
+    ConsoleCommandInfo {
+        name: "tp",
+        args: "<y>",
+        help: "move the player to a height",
+        completions: &[],
+    },
+];
+
+/// The flapping character: its spawn, its physics body and its inputs.
+pub struct PlayerPlugin;
+
+impl Plugin for PlayerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HitboxDefinitions>()
+            .init_resource::<CharacterRoster>()
+            .init_resource::<Profile>()
+            .init_resource::<FlapBudget>()
+            .register_console_commands(PLAYER_CONSOLE_COMMANDS)
+            .add_startup_system(load_selected_character)
+            .add_system(handle_player_console_commands)
+            .add_enter_system(AppState::InGame(InGameState::Initialization), spawn_player)
+            .add_enter_system_set(
+                AppState::InGame(InGameState::ReadyToStart),
+                ConditionSet::new()
+                    .with_system(reset_player_state)
+                    .with_system(reset_player_animation)
+                    .with_system(respawn_player_on_character_change)
+                    .into(),
+            )
+            .add_enter_system_set(
+                AppState::InGame(InGameState::Playing),
+                ConditionSet::new()
+                    .with_system(enable_player_gravity)
+                    .with_system(reset_player_animation)
+                    .into(),
+            )
+            .add_system_set_to_stage(
+                GameStage::Ui,
+                ConditionSet::new()
+                    .run_in_state(AppState::InGame(InGameState::Playing))
+                    .run_if(no_screen_transition)
+                    .with_system(play_player_flap_animation)
+                    .into(),
+            )
+            .add_enter_system(
+                AppState::InGame(InGameState::GameOver),
+                play_player_death_animation,
+            )
+            .add_system_set_to_stage(
+                GameStage::Input,
+                ConditionSet::new()
+                    .run_in_state(AppState::InGame(InGameState::Playing))
+                    .run_if(no_screen_transition)
+                    .with_system(player_jump)
+                    .into(),
+            )
+            // MENU - CHARACTER SELECT
+            .add_enter_system(
+                AppState::Menu(MenuState::CharacterSelect),
+                start_character_select,
+            )
+            .add_system(
+                browse_characters_on_input
+                    .run_in_state(AppState::Menu(MenuState::CharacterSelect))
+                    .run_if(no_screen_transition),
+            );
+    }
+}
 
 #[derive(Component)]
 pub struct Player;
 
+/// The roster character the player was spawned as, with its physics.
+#[derive(Component, Debug, Clone)]
+pub struct PlayerCharacter {
+    pub id: String,
+    pub gravity_scale: f32,
+    pub jump_impulse: f32,
+}
+
+/// The sprite of the player, a child of the [`Player`] entity.
+#[derive(Component)]
+pub struct PlayerSprite;
+
+/// Game over colliders are ignored by the player while it has this component.
+#[derive(Component)]
+pub struct Invulnerable;
+
+/// Game over colliders are absorbed by the shield power-up while the player has this component,
+/// the first one breaking the shield. Kept apart from [`Invulnerable`], which the console toggles.
+#[derive(Component)]
+pub struct Shielded;
+
+/// Flaps the player has left, unlimited when `None`.
+#[derive(Resource, Default, Debug, Clone, Copy)]
+pub struct FlapBudget(pub Option<u32>);
+
 #[derive(Resource, Default, Debug, Clone, Copy)]
 pub struct PlayerScore {
     pub value: u16,
@@ -28,58 +149,125 @@ pub struct PlayerScore {
 pub fn spawn_player(
     mut commands: Commands,
     game_assets: Res<GameAssets>,
-    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    hitboxes: Res<HitboxDefinitions>,
+    roster: Res<CharacterRoster>,
+    selected: Res<SelectedCharacter>,
 ) {
-    let texture_atlas = TextureAtlas::from_grid(
-        game_assets.characters_image.clone(),
-        Vec2::new(24.0, 24.0),
-        9,
-        3,
-        Some(Vec2::splat(2.0)),
-        None,
+    spawn_player_entity(
+        &mut commands,
+        game_assets.characters_atlas.clone(),
+        &hitboxes,
+        roster.get_or_first(&selected.id),
     );
-    let texture_atlas_handle = texture_atlases.add(texture_atlas);
-    let animation_indices = AnimationIndices {
-        first: 24,
-        last: 26,
-    };
-    commands.spawn((
-        Player,
-        SpriteSheetBundle {
-            texture_atlas: texture_atlas_handle,
-            sprite: TextureAtlasSprite::new(animation_indices.first),
-            ..Default::default()
-        },
-        animation_indices,
-        AnimationTimer(Timer::from_seconds(0.16, TimerMode::Repeating)),
-        Collider::ball(10.0),
-        RigidBody::Dynamic,
-        Ccd::enabled(),
-        GravityScale(0.0),
-        CollisionGroups::new(
-            COLLISION_GROUP_PLAYER,
-            COLLISION_GROUP_GAME_OVER.union(COLLISION_GROUP_OPENING),
-        ),
-        Velocity::zero(),
-        LockedAxes::from(LockedAxes::TRANSLATION_LOCKED_X | LockedAxes::ROTATION_LOCKED),
-        ActiveEvents::COLLISION_EVENTS,
-    ));
+}
+
+pub fn respawn_player_on_character_change(
+    mut commands: Commands,
+    q_player: Query<(Entity, &PlayerCharacter)>,
+    game_assets: Res<GameAssets>,
+    hitboxes: Res<HitboxDefinitions>,
+    roster: Res<CharacterRoster>,
+    selected: Res<SelectedCharacter>,
+) {
+    let character = roster.get_or_first(&selected.id);
+    if let Ok((player, player_character)) = q_player.get_single() {
+        if player_character.id == character.id {
+            return;
+        }
+        // colliders are built when the body is added, a new character needs a new entity
+        commands.entity(player).despawn_recursive();
+        spawn_player_entity(
+            &mut commands,
+            game_assets.characters_atlas.clone(),
+            &hitboxes,
+            character,
+        );
+    }
+}
+
+fn spawn_player_entity(
+    commands: &mut Commands,
+    characters_atlas: Handle<TextureAtlas>,
+    hitboxes: &HitboxDefinitions,
+    character: &CharacterDefinition,
+) {
+    let hitbox = hitboxes
+        .character(&character.id)
+        .cloned()
+        .unwrap_or(Hitbox::Circle {
+            radius: character.collider_radius,
+        });
+    let animator = Animator::new(character.clips.clone(), CLIP_IDLE);
+    // the sprite is a child, so it can tilt and stretch without moving the collider
+    commands
+        .spawn((
+            Player,
+            PlayerCharacter {
+                id: character.id.clone(),
+                gravity_scale: character.gravity_scale(),
+                jump_impulse: character.jump_impulse(),
+            },
+            SpatialBundle::default(),
+            PhysicsBody::dynamic(hitbox).with_groups(
+                COLLISION_GROUP_PLAYER,
+                COLLISION_GROUP_GAME_OVER
+                    | COLLISION_GROUP_PIPE
+                    | COLLISION_GROUP_OPENING
+                    | COLLISION_GROUP_PICKUP
+                    | COLLISION_GROUP_HAZARD,
+            ),
+            GravityScale(0.0),
+            Velocity::default(),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                PlayerSprite,
+                SpriteSheetBundle {
+                    texture_atlas: characters_atlas,
+                    sprite: TextureAtlasSprite::new(animator.atlas_index().unwrap_or(0)),
+                    ..Default::default()
+                },
+                animator,
+            ));
+        });
+}
+
+pub fn flap_requested(keyboard_input: &Input<KeyCode>, mouse_input: &Input<MouseButton>) -> bool {
+    keyboard_input.just_pressed(KeyCode::Space) || mouse_input.just_pressed(MouseButton::Left)
 }
 
 pub fn player_jump(
-    mut q_player: Query<&mut Velocity, With<Player>>,
+    mut q_player: Query<(Entity, &mut Velocity, &Transform, &PlayerCharacter), With<Player>>,
+    mut ev_game: EventWriter<GameEvent>,
+    mut flap_budget: ResMut<FlapBudget>,
     keyboard_input: Res<Input<KeyCode>>,
     mouse_input: Res<Input<MouseButton>>,
+    gravity: Res<Gravity>,
+    game_tick: Res<GameTick>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::Space) || mouse_input.just_pressed(MouseButton::Left) {
-        if let Ok(mut vel) = q_player.get_single_mut() {
-            vel.linvel = Vec2::new(0.0, JUMP_IMPULSE_VALUE);
+    if flap_requested(&keyboard_input, &mouse_input) && flap_budget.0 != Some(0) {
+        if let Ok((player, mut vel, t, character)) = q_player.get_single_mut() {
+            if let Some(flaps) = flap_budget.0.as_mut() {
+                *flaps -= 1;
+            }
+            // flaps push against the gravity, whichever way it pulls
+            let direction = if gravity.0.y > 0.0 { -1.0 } else { 1.0 };
+            vel.0 = Vec2::new(0.0, character.jump_impulse * direction);
+            ev_game.send(GameEvent::PlayerFlapped {
+                player,
+                position: t.translation.truncate(),
+                tick: game_tick.0,
+            });
         }
     }
 }
 
-pub fn enable_player_gravity(mut q_player: Query<&mut GravityScale, With<Player>>) {
-    q_player.single_mut().0 = 1.0;
+pub fn enable_player_gravity(
+    mut q_player: Query<(&mut GravityScale, &PlayerCharacter), With<Player>>,
+) {
+    if let Ok((mut gravity_scale, character)) = q_player.get_single_mut() {
+        gravity_scale.0 = character.gravity_scale;
+    }
 }
 
 pub fn reset_player_state(
@@ -89,8 +277,35 @@ pub fn reset_player_state(
         gs.0 = 0.0;
         t.translation = Vec3::ZERO;
         t.rotation = Quat::IDENTITY;
-        v.linvel = Vec2::ZERO;
-        v.angvel = 0.0;
+        v.0 = Vec2::ZERO;
+    }
+}
+
+pub fn reset_player_animation(mut q_sprite: Query<&mut Animator, With<PlayerSprite>>) {
+    if let Ok(mut animator) = q_sprite.get_single_mut() {
+        animator.set(CLIP_IDLE);
+    }
+}
+
+/// Plays the flap clip from its start on every jump, it then goes on gliding.
+pub fn play_player_flap_animation(
+    mut ev_game: EventReader<GameEvent>,
+    mut q_sprite: Query<&mut Animator, With<PlayerSprite>>,
+) {
+    for _ in ev_game
+        .iter()
+        .filter(|ev| matches!(ev, GameEvent::PlayerFlapped { .. }))
+    {
+        if let Ok(mut animator) = q_sprite.get_single_mut() {
+            animator.play(CLIP_FLAP);
+        }
+    }
+}
+
+/// Plays the hurt clip, followed by the dead one.
+pub fn play_player_death_animation(mut q_sprite: Query<&mut Animator, With<PlayerSprite>>) {
+    if let Ok(mut animator) = q_sprite.get_single_mut() {
+        animator.play(CLIP_HURT);
     }
 }
 
@@ -104,8 +319,48 @@ pub fn handle_game_event_player_passed_opening(
 ) {
     for _ in ev_game
         .iter()
-        .filter(|&ev| ev == &GameEvent::PlayerPassedAnOpening)
+        .filter(|ev| matches!(ev, GameEvent::PlayerPassedAnOpening { .. }))
     {
-        player_score.value += 1;
+        player_score.value = player_score.value.saturating_add(1);
+    }
+}
+
+pub fn handle_player_console_commands(
+    mut commands: Commands,
+    mut ev_commands: EventReader<ConsoleCommand>,
+    mut ev_console: EventWriter<ConsoleLine>,
+    mut q_player: Query<
+        (Entity, &mut Transform, &mut Velocity, Option<&Invulnerable>),
+        With<Player>,
+    >,
+    mut player_score: ResMut<PlayerScore>,
+) {
+    for command in ev_commands.iter() {
+        let result = match (command.name.as_str(), q_player.get_single_mut()) {
+            ("score", _) => command.arg::<u16>(0).map(|score| {
+                player_score.value = score;
+                format!("score set to {}", score)
+            }),
+            ("god", Ok((player, _, _, invulnerable))) => {
+                if invulnerable.is_some() {
+                    commands.entity(player).remove::<Invulnerable>();
+                    Ok("god mode off".to_string())
+                } else {
+                    commands.entity(player).insert(Invulnerable);
+                    Ok("god mode on".to_string())
+                }
+            }
+            ("tp", Ok((_, mut t, mut v, _))) => command.arg::<f32>(0).and_then(|y| {
+                if !y.is_finite() {
+                    return Err("the height cannot be infinite or NaN".to_string());
+                }
+                t.translation.y = y;
+                v.0 = Vec2::ZERO;
+                Ok(format!("player moved to y {}", y))
+            }),
+            ("god" | "tp", Err(_)) => Err("no player spawned".to_string()),
+            _ => continue,
+        };
+        ev_console.send(result.into());
     }
 }
@@ -0,0 +1,554 @@
+use std::collections::BTreeMap;
+
+use bevy::{
+    prelude::{
+        App, BuildChildren, Color, Commands, Component, DespawnRecursiveExt, Entity, EventReader,
+        EventWriter, Plugin, Query, Res, ResMut, Resource, Transform, Vec2, Vec3, With, Without,
+    },
+    sprite::{Sprite, SpriteBundle, TextureAtlasSprite},
+    text::{Text, Text2dBundle, TextAlignment, TextStyle},
+};
+use bevy_turborand::{DelegatedRng, GlobalRng};
+use iyes_loopless::prelude::{AppLooplessStateExt, ConditionSet};
+
+use crate::{
+    app_states::{AppState, InGameState},
+    assets::GameAssets,
+    camera::Readable,
+    consts::{
+        COLLISION_GROUP_PICKUP, COLLISION_GROUP_PIPE, COLLISION_GROUP_PLAYER, GAME_WIDTH,
+        PLAYER_SPRITE_SIZE,
+    },
+    events::DeathCause,
+    game::{GameTime, HorizontalMove, TimeScale},
+    obstacles::{ObstacleSpawned, OpeningCrossing},
+    physics::{ContactEvent, Hitbox, PhysicsBody, Velocity},
+    player::{Player, PlayerCharacter, PlayerSprite, Shielded},
+    rewind::DroppedOnRewind,
+    schedule::GameStage,
+    transitions::no_screen_transition,
+};
+
+pub const POWER_UP_SHIELD: &str = "shield";
+pub const POWER_UP_SHRINK: &str = "shrink";
+pub const POWER_UP_SLOW_MOTION: &str = "slowmo";
+pub const POWER_UP_MAGNET: &str = "magnet";
+pub const POWER_UP_GHOST: &str = "ghost";
+
+const BUILTIN_POWER_UPS: [PowerUpDefinition; 5] = [
+    PowerUpDefinition {
+        id: POWER_UP_SHIELD,
+        name: "Shield",
+        icon: "O",
+        color: Color::rgb(0.35, 0.7, 1.0),
+        seconds: 10.0,
+        weight: 1.0,
+    },
+    PowerUpDefinition {
+        id: POWER_UP_SHRINK,
+        name: "Shrink",
+        icon: "s",
+        color: Color::rgb(0.55, 0.9, 0.4),
+        seconds: 8.0,
+        weight: 1.0,
+    },
+    PowerUpDefinition {
+        id: POWER_UP_SLOW_MOTION,
+        name: "Slow-mo",
+        icon: "T",
+        color: Color::rgb(0.95, 0.8, 0.3),
+        seconds: 5.0,
+        weight: 0.8,
+    },
+    PowerUpDefinition {
+        id: POWER_UP_MAGNET,
+        name: "Magnet",
+        icon: "U",
+        color: Color::rgb(0.95, 0.35, 0.35),
+        seconds: 10.0,
+        weight: 1.0,
+    },
+    PowerUpDefinition {
+        id: POWER_UP_GHOST,
+        name: "Ghost",
+        icon: "G",
+        color: Color::rgb(0.8, 0.6, 1.0),
+        seconds: 4.0,
+        weight: 0.6,
+    },
+];
+
+// a pickup spawns halfway to the next pipe, they are at least twice as far apart
+const PICKUP_OFFSET_X: f32 = 90.0;
+const PICKUP_MAX_OFFSET_Y: f32 = 30.0;
+const PICKUP_SPAWN_CHANCE: f32 = 0.2;
+const PICKUP_SIZE: f32 = 10.0;
+const PICKUP_Z: f32 = 5.0;
+
+const SHIELD_BREAK_GRACE_SECONDS: f32 = 0.75;
+const SHRINK_SCALE: f32 = 0.6;
+const SLOW_MOTION_TIME_SCALE: f32 = 0.6;
+const MAGNET_RADIUS: f32 = 90.0;
+const MAGNET_SPEED: f32 = 220.0;
+const GHOST_ALPHA: f32 = 0.45;
+
+const TIME_SCALE_SLOW_MOTION: &str = "slowmo";
+
+/// Power-ups picked up between the pipes, each one active for a while. Picking up and timing
+/// power-ups is generic, the effect of a power-up is left to systems reading [`PowerUpEvent`]s,
+/// so new ones are added with [`RegisterPowerUps`] and their own systems.
+pub struct PowerUpsPlugin;
+
+impl Plugin for PowerUpsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_power_ups(BUILTIN_POWER_UPS)
+            .init_resource::<ShieldState>()
+            .add_enter_system(AppState::InGame(InGameState::ReadyToStart), reset_power_ups)
+            .add_system_set_to_stage(
+                GameStage::Movement,
+                ConditionSet::new()
+                    .run_in_state(AppState::InGame(InGameState::Playing))
+                    .run_if(no_screen_transition)
+                    .with_system(attract_magnetic_pickups)
+                    .with_system(despawn_passed_pickups)
+                    .into(),
+            )
+            .add_system_set_to_stage(
+                GameStage::CollisionDispatch,
+                ConditionSet::new()
+                    .run_in_state(AppState::InGame(InGameState::Playing))
+                    .run_if(no_screen_transition)
+                    .with_system(spawn_power_up_pickups)
+                    .with_system(collect_power_ups)
+                    .with_system(absorb_hit_with_shield)
+                    .with_system(tick_power_ups)
+                    .into(),
+            )
+            // effects are also turned off when the run is reset, whatever the state
+            .add_system_to_stage(GameStage::Ui, apply_shield)
+            .add_system_to_stage(GameStage::Ui, apply_shrink)
+            .add_system_to_stage(GameStage::Ui, apply_slow_motion)
+            .add_system_to_stage(GameStage::Ui, apply_ghost);
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PowerUpDefinition {
+    pub id: &'static str,
+    pub name: &'static str,
+    /// Drawn on the pickup.
+    pub icon: &'static str,
+    pub color: Color,
+    pub seconds: f32,
+    /// Odds of being the power-up of a pickup, relative to the other ones.
+    pub weight: f32,
+}
+
+/// The power-ups pickups are drawn from.
+#[derive(Resource, Default)]
+pub struct PowerUpRegistry(BTreeMap<&'static str, PowerUpDefinition>);
+
+impl PowerUpRegistry {
+    pub fn get(&self, id: &str) -> Option<&PowerUpDefinition> {
+        self.0.get(id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &PowerUpDefinition> {
+        self.0.values()
+    }
+
+    /// Picks a power-up by weight, `roll` being between 0 and 1.
+    fn pick(&self, roll: f32) -> Option<&PowerUpDefinition> {
+        let total: f32 = self.iter().map(|p| p.weight.max(0.0)).sum();
+        let mut target = roll * total;
+        self.iter().filter(|p| p.weight > 0.0).find(|p| {
+            target -= p.weight;
+            target < 0.0
+        })
+    }
+}
+
+pub trait RegisterPowerUps {
+    /// Makes pickups hand out these power-ups, their effects being applied by the systems of the
+    /// registering plugin on [`PowerUpEvent`]s.
+    fn register_power_ups(
+        &mut self,
+        power_ups: impl IntoIterator<Item = PowerUpDefinition>,
+    ) -> &mut Self;
+}
+
+impl RegisterPowerUps for App {
+    fn register_power_ups(
+        &mut self,
+        power_ups: impl IntoIterator<Item = PowerUpDefinition>,
+    ) -> &mut Self {
+        self.add_event::<PowerUpEvent>()
+            .init_resource::<PowerUpRegistry>()
+            .init_resource::<ActivePowerUps>();
+        let mut registry = self.world.resource_mut::<PowerUpRegistry>();
+        for definition in power_ups {
+            registry.0.insert(definition.id, definition);
+        }
+        self
+    }
+}
+
+/// A power-up effect starting or ending. Picking up an active power-up again only restarts its
+/// timer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerUpEvent {
+    Started(&'static str),
+    Ended(&'static str),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PowerUpTimer {
+    pub remaining: f32,
+    pub seconds: f32,
+}
+
+/// The power-ups in effect, with the time they have left on the gameplay clock.
+#[derive(Resource, Default)]
+pub struct ActivePowerUps(BTreeMap<&'static str, PowerUpTimer>);
+
+impl ActivePowerUps {
+    pub fn is_active(&self, id: &str) -> bool {
+        self.0.contains_key(id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &PowerUpTimer)> {
+        self.0.iter().map(|(id, timer)| (*id, timer))
+    }
+
+    /// Ends the power-up within `seconds`, when it has more time left.
+    pub fn cut_short(&mut self, id: &str, seconds: f32) {
+        if let Some(timer) = self.0.get_mut(id) {
+            timer.remaining = timer.remaining.min(seconds);
+        }
+    }
+}
+
+/// A pickup handing out the power-up with this id.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct PowerUpPickup(pub &'static str);
+
+/// Collectibles pulled towards the player by the magnet.
+#[derive(Component)]
+pub struct Magnetic;
+
+#[derive(Resource, Default)]
+pub struct ShieldState {
+    broken: bool,
+}
+
+/// The hitbox of the player before it shrank.
+#[derive(Component)]
+pub struct Shrunk(Hitbox);
+
+pub fn reset_power_ups(
+    mut commands: Commands,
+    mut active: ResMut<ActivePowerUps>,
+    mut ev_power_ups: EventWriter<PowerUpEvent>,
+    q_pickups: Query<Entity, With<PowerUpPickup>>,
+) {
+    for e in q_pickups.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+    for (id, _) in std::mem::take(&mut active.0) {
+        ev_power_ups.send(PowerUpEvent::Ended(id));
+    }
+}
+
+/// Rolls for a pickup each time an obstacle is spawned.
+pub fn spawn_power_up_pickups(
+    mut commands: Commands,
+    mut ev_obstacles: EventReader<ObstacleSpawned>,
+    mut global_rng: ResMut<GlobalRng>,
+    registry: Res<PowerUpRegistry>,
+    game_assets: Res<GameAssets>,
+    q_openings: Query<&Transform, With<OpeningCrossing>>,
+    q_pickups: Query<Entity, With<PowerUpPickup>>,
+) {
+    let obstacle_spawned = ev_obstacles.iter().count() > 0;
+    if !obstacle_spawned || !q_pickups.is_empty() || global_rng.f32() >= PICKUP_SPAWN_CHANCE {
+        return;
+    }
+
+    let newest_opening = q_openings
+        .iter()
+        .map(|t| t.translation)
+        .max_by(|a, b| a.x.total_cmp(&b.x));
+    let (opening, power_up) = match (newest_opening, registry.pick(global_rng.f32())) {
+        (Some(opening), Some(power_up)) => (opening, power_up),
+        _ => return,
+    };
+    let offset_y = (global_rng.f32() * 2.0 - 1.0) * PICKUP_MAX_OFFSET_Y;
+    commands
+        .spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: power_up.color,
+                    custom_size: Some(Vec2::splat(PICKUP_SIZE)),
+                    ..Default::default()
+                },
+                transform: Transform::from_translation(Vec3::new(
+                    opening.x + PICKUP_OFFSET_X,
+                    opening.y + offset_y,
+                    PICKUP_Z,
+                )),
+                ..Default::default()
+            },
+            HorizontalMove { factor: 1.0 },
+            PhysicsBody::kinematic(Hitbox::Circle {
+                radius: PICKUP_SIZE / 2.0 + 1.0,
+            })
+            .sensor()
+            .with_groups(COLLISION_GROUP_PICKUP, COLLISION_GROUP_PLAYER),
+            PowerUpPickup(power_up.id),
+            Magnetic,
+            DroppedOnRewind,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text2dBundle {
+                    text: Text::from_section(
+                        power_up.icon,
+                        TextStyle {
+                            font: game_assets.font.clone(),
+                            font_size: 8.0,
+                            color: Color::BLACK,
+                        },
+                    )
+                    .with_alignment(TextAlignment::CENTER),
+                    transform: Transform::from_xyz(0.0, 0.0, 1.0),
+                    ..Default::default()
+                },
+                Readable::in_place(),
+            ));
+        });
+}
+
+pub fn despawn_passed_pickups(
+    mut commands: Commands,
+    q_pickups: Query<(Entity, &Transform), With<PowerUpPickup>>,
+) {
+    for (e, t) in q_pickups.iter() {
+        if t.translation.x < -GAME_WIDTH / 2.0 - PICKUP_SIZE {
+            commands.entity(e).despawn_recursive();
+        }
+    }
+}
+
+pub fn attract_magnetic_pickups(
+    mut q_magnetic: Query<&mut Transform, (With<Magnetic>, Without<Player>)>,
+    q_player: Query<&Transform, With<Player>>,
+    active: Res<ActivePowerUps>,
+    game_time: Res<GameTime>,
+) {
+    if !active.is_active(POWER_UP_MAGNET) {
+        return;
+    }
+    let player = match q_player.get_single() {
+        Ok(t) => t.translation.truncate(),
+        Err(_) => return,
+    };
+    let step = MAGNET_SPEED * game_time.delta_seconds();
+    for mut t in q_magnetic.iter_mut() {
+        let to_player = player - t.translation.truncate();
+        let distance = to_player.length();
+        if distance > 0.0 && distance < MAGNET_RADIUS {
+            let pull = to_player / distance * step.min(distance);
+            t.translation += pull.extend(0.0);
+        }
+    }
+}
+
+pub fn collect_power_ups(
+    mut commands: Commands,
+    mut ev_contacts: EventReader<ContactEvent>,
+    mut ev_power_ups: EventWriter<PowerUpEvent>,
+    mut active: ResMut<ActivePowerUps>,
+    registry: Res<PowerUpRegistry>,
+    q_player: Query<(), With<Player>>,
+    q_pickups: Query<&PowerUpPickup>,
+) {
+    for contact in ev_contacts.iter() {
+        let (ent_a, ent_b) = match *contact {
+            ContactEvent::Started(ent_a, ent_b) => (ent_a, ent_b),
+            ContactEvent::Stopped(..) => continue,
+        };
+        let pickup = if q_player.contains(ent_a) {
+            ent_b
+        } else if q_player.contains(ent_b) {
+            ent_a
+        } else {
+            continue;
+        };
+        if let Ok(&PowerUpPickup(id)) = q_pickups.get(pickup) {
+            commands.entity(pickup).despawn_recursive();
+            if let Some(definition) = registry.get(id) {
+                let timer = PowerUpTimer {
+                    remaining: definition.seconds,
+                    seconds: definition.seconds,
+                };
+                if active.0.insert(definition.id, timer).is_none() {
+                    ev_power_ups.send(PowerUpEvent::Started(definition.id));
+                }
+            }
+        }
+    }
+}
+
+pub fn tick_power_ups(
+    mut active: ResMut<ActivePowerUps>,
+    mut ev_power_ups: EventWriter<PowerUpEvent>,
+    game_time: Res<GameTime>,
+) {
+    if active.0.is_empty() {
+        return;
+    }
+    let dt = game_time.delta_seconds();
+    active.0.retain(|id, timer| {
+        timer.remaining -= dt;
+        if timer.remaining <= 0.0 {
+            ev_power_ups.send(PowerUpEvent::Ended(*id));
+        }
+        timer.remaining > 0.0
+    });
+}
+
+/// The first hit taken behind the shield breaks it, the player bouncing off the ground. The
+/// broken shield lasts a short while for the player to get clear of the collider.
+pub fn absorb_hit_with_shield(
+    mut ev_contacts: EventReader<ContactEvent>,
+    mut active: ResMut<ActivePowerUps>,
+    mut shield: ResMut<ShieldState>,
+    mut q_player: Query<(&mut Velocity, &PlayerCharacter), With<Player>>,
+    q_colliders: Query<&DeathCause>,
+) {
+    for contact in ev_contacts.iter() {
+        let (ent_a, ent_b) = match *contact {
+            ContactEvent::Started(ent_a, ent_b) => (ent_a, ent_b),
+            ContactEvent::Stopped(..) => continue,
+        };
+        if shield.broken || !active.is_active(POWER_UP_SHIELD) {
+            continue;
+        }
+        let (player, collider) = if q_player.contains(ent_a) {
+            (ent_a, ent_b)
+        } else {
+            (ent_b, ent_a)
+        };
+        if let (Ok((mut velocity, character)), Ok(cause)) =
+            (q_player.get_mut(player), q_colliders.get(collider))
+        {
+            shield.broken = true;
+            active.cut_short(POWER_UP_SHIELD, SHIELD_BREAK_GRACE_SECONDS);
+            if *cause == DeathCause::Ground {
+                velocity.0.y = character.jump_impulse;
+            }
+        }
+    }
+}
+
+pub fn apply_shield(
+    mut commands: Commands,
+    mut ev_power_ups: EventReader<PowerUpEvent>,
+    mut shield: ResMut<ShieldState>,
+    q_player: Query<Entity, With<Player>>,
+) {
+    for ev in ev_power_ups.iter() {
+        let player = match q_player.get_single() {
+            Ok(player) => player,
+            Err(_) => continue,
+        };
+        match *ev {
+            PowerUpEvent::Started(POWER_UP_SHIELD) => {
+                *shield = ShieldState::default();
+                commands.entity(player).insert(Shielded);
+            }
+            PowerUpEvent::Ended(POWER_UP_SHIELD) => {
+                commands.entity(player).remove::<Shielded>();
+                *shield = ShieldState::default();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Shrinks the collider of the player along with its sprite.
+pub fn apply_shrink(
+    mut commands: Commands,
+    mut ev_power_ups: EventReader<PowerUpEvent>,
+    mut q_player: Query<(Entity, &mut PhysicsBody, Option<&Shrunk>), With<Player>>,
+    mut q_sprite: Query<&mut TextureAtlasSprite, With<PlayerSprite>>,
+) {
+    for ev in ev_power_ups.iter() {
+        let (player, mut body, shrunk) = match q_player.get_single_mut() {
+            Ok(player) => player,
+            Err(_) => continue,
+        };
+        match (*ev, shrunk) {
+            (PowerUpEvent::Started(POWER_UP_SHRINK), None) => {
+                commands.entity(player).insert(Shrunk(body.hitbox.clone()));
+                body.hitbox = body.hitbox.scaled(SHRINK_SCALE);
+                for mut sprite in q_sprite.iter_mut() {
+                    let size = sprite
+                        .custom_size
+                        .unwrap_or(Vec2::splat(PLAYER_SPRITE_SIZE));
+                    sprite.custom_size = Some(size * SHRINK_SCALE);
+                }
+            }
+            (PowerUpEvent::Ended(POWER_UP_SHRINK), Some(Shrunk(hitbox))) => {
+                body.hitbox = hitbox.clone();
+                commands.entity(player).remove::<Shrunk>();
+                for mut sprite in q_sprite.iter_mut() {
+                    sprite.custom_size = sprite.custom_size.map(|size| size / SHRINK_SCALE);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn apply_slow_motion(
+    mut ev_power_ups: EventReader<PowerUpEvent>,
+    mut time_scale: ResMut<TimeScale>,
+) {
+    for ev in ev_power_ups.iter() {
+        match *ev {
+            PowerUpEvent::Started(POWER_UP_SLOW_MOTION) => {
+                time_scale.set_factor(TIME_SCALE_SLOW_MOTION, SLOW_MOTION_TIME_SCALE)
+            }
+            PowerUpEvent::Ended(POWER_UP_SLOW_MOTION) => {
+                time_scale.remove_factor(TIME_SCALE_SLOW_MOTION)
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Lets the player fly through the pipes, the bounds of the screen still end the run.
+pub fn apply_ghost(
+    mut ev_power_ups: EventReader<PowerUpEvent>,
+    mut q_player: Query<&mut PhysicsBody, With<Player>>,
+    mut q_sprite: Query<&mut TextureAtlasSprite, With<PlayerSprite>>,
+) {
+    for ev in ev_power_ups.iter() {
+        let (ghost, alpha) = match *ev {
+            PowerUpEvent::Started(POWER_UP_GHOST) => (true, GHOST_ALPHA),
+            PowerUpEvent::Ended(POWER_UP_GHOST) => (false, 1.0),
+            _ => continue,
+        };
+        if let Ok(mut body) = q_player.get_single_mut() {
+            if ghost {
+                body.filters &= !COLLISION_GROUP_PIPE;
+            } else {
+                body.filters |= COLLISION_GROUP_PIPE;
+            }
+        }
+        for mut sprite in q_sprite.iter_mut() {
+            sprite.color.set_a(alpha);
+        }
+    }
+}
@@ -0,0 +1,387 @@
+use std::collections::{HashSet, VecDeque};
+
+use bevy::{
+    ecs::system::SystemParam,
+    prelude::{
+        App, Commands, Component, DespawnRecursiveExt, Entity, EventWriter, Input, KeyCode, Plugin,
+        Query, Res, ResMut, Resource, Transform, Vec2, Vec3, With, Without,
+    },
+    sprite::Sprite,
+};
+use iyes_loopless::prelude::{AppLooplessStateExt, ConditionSet, IntoConditionalSystem};
+
+use crate::{
+    app_states::{AppState, InGameState},
+    assets::GameAssets,
+    consts::{REWIND_BUFFER_SECONDS, REWIND_CHARGES},
+    game::{GameSpeed, GameTime, PracticeMode, TimeScale},
+    hitboxes::{HitboxDefinitions, HitboxSettings},
+    obstacles::{
+        spawn_obstacle_entity, spawn_obstacle_opening_sensor_entity, Obstacle, ObstacleSettings,
+        ObstaclesData, OpeningCrossing,
+    },
+    physics::Velocity,
+    player::{Player, PlayerScore},
+    schedule::GameStage,
+    stats::RunStats,
+    transitions::{no_screen_transition, StateChangeRequest},
+    world::Ground,
+};
+
+const REWIND_KEY: KeyCode = KeyCode::R;
+// snapshots dropped per rewound frame
+const REWIND_SPEED: usize = 2;
+const PRACTICE_RETRY_SECONDS: f32 = 3.0;
+
+const TIME_SCALE_REWIND: &str = "rewind";
+
+/// Holding `R` plays the last seconds of the run backwards, a limited number of times per run.
+/// In practice mode rewinds are unlimited, and a run can be retried from shortly before the death.
+pub struct RewindPlugin;
+
+impl Plugin for RewindPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Rewind>()
+            .init_resource::<RewindBuffer>()
+            .add_enter_system(AppState::InGame(InGameState::ReadyToStart), reset_rewind)
+            .add_enter_system(AppState::InGame(InGameState::Playing), apply_pending_retry)
+            .add_system_set_to_stage(
+                GameStage::Ui,
+                ConditionSet::new()
+                    .run_in_state(AppState::InGame(InGameState::Playing))
+                    .run_if(no_screen_transition)
+                    .with_system(rewind_or_record_world)
+                    .into(),
+            )
+            .add_system(
+                retry_before_death_on_input
+                    .run_in_state(AppState::InGame(InGameState::GameOver))
+                    .run_if(no_screen_transition),
+            );
+    }
+}
+
+/// Entities left out of the [`WorldSnapshot`]s, despawned when the world is rewound.
+#[derive(Component)]
+pub struct DroppedOnRewind;
+
+#[derive(Resource, Default)]
+pub struct Rewind {
+    charges: u8,
+    active: bool,
+    pending_retry: Option<WorldSnapshot>,
+}
+
+impl Rewind {
+    pub fn charges(&self) -> u8 {
+        self.charges
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+}
+
+/// Snapshots of the last seconds of the run, oldest first.
+#[derive(Resource, Default)]
+pub struct RewindBuffer(VecDeque<WorldSnapshot>);
+
+#[derive(Debug, Clone)]
+pub struct WorldSnapshot {
+    /// Gameplay clock elapsed seconds.
+    time: f32,
+    player_translation: Vec3,
+    player_velocity: Vec2,
+    obstacles: Vec<(Entity, ObstacleSnapshot)>,
+    ground_x: Vec<(Entity, f32)>,
+    game_speed: f32,
+    obstacles_data: ObstaclesData,
+    score: u16,
+}
+
+impl WorldSnapshot {
+    /// Refers to the obstacles respawned by a restore by their new entity.
+    fn replace_obstacles(&mut self, respawned: &[(Entity, Entity)]) {
+        for (entity, _) in self.obstacles.iter_mut() {
+            if let Some(&(_, new_entity)) = respawned.iter().find(|(old, _)| old == entity) {
+                *entity = new_entity;
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ObstacleSnapshot {
+    Pipe {
+        position: Vec2,
+        flip_y: bool,
+    },
+    Opening {
+        position: Vec2,
+        crossing: OpeningCrossing,
+    },
+}
+
+impl ObstacleSnapshot {
+    fn position(&self) -> Vec2 {
+        match *self {
+            ObstacleSnapshot::Pipe { position, .. }
+            | ObstacleSnapshot::Opening { position, .. } => position,
+        }
+    }
+}
+
+/// The part of the world a [`WorldSnapshot`] captures and restores.
+#[derive(SystemParam)]
+pub struct RewindableWorld<'w, 's> {
+    commands: Commands<'w, 's>,
+    game_speed: ResMut<'w, GameSpeed>,
+    obstacles_data: ResMut<'w, ObstaclesData>,
+    player_score: ResMut<'w, PlayerScore>,
+    game_assets: Res<'w, GameAssets>,
+    hitboxes: Res<'w, HitboxDefinitions>,
+    hitbox_settings: Res<'w, HitboxSettings>,
+    obstacle_settings: Res<'w, ObstacleSettings>,
+    q_player: Query<'w, 's, (&'static mut Transform, &'static mut Velocity), With<Player>>,
+    q_obstacles: Query<
+        'w,
+        's,
+        (
+            Entity,
+            &'static Obstacle,
+            &'static mut Transform,
+            Option<&'static Sprite>,
+            Option<&'static mut OpeningCrossing>,
+        ),
+        Without<Player>,
+    >,
+    q_ground: Query<
+        'w,
+        's,
+        (Entity, &'static mut Transform),
+        (With<Ground>, Without<Player>, Without<Obstacle>),
+    >,
+    q_dropped: Query<'w, 's, Entity, With<DroppedOnRewind>>,
+}
+
+impl<'w, 's> RewindableWorld<'w, 's> {
+    fn capture(&self, time: f32) -> Option<WorldSnapshot> {
+        let (player_transform, player_velocity) = self.q_player.get_single().ok()?;
+        let obstacles = self
+            .q_obstacles
+            .iter()
+            .filter_map(|(e, obstacle, t, sprite, crossing)| {
+                let position = t.translation.truncate();
+                match (obstacle, crossing) {
+                    (Obstacle::GameOver, _) => Some((
+                        e,
+                        ObstacleSnapshot::Pipe {
+                            position,
+                            flip_y: sprite.map_or(false, |s| s.flip_y),
+                        },
+                    )),
+                    (Obstacle::Opening, Some(&crossing)) => {
+                        Some((e, ObstacleSnapshot::Opening { position, crossing }))
+                    }
+                    _ => None,
+                }
+            })
+            .collect();
+        Some(WorldSnapshot {
+            time,
+            player_translation: player_transform.translation,
+            player_velocity: player_velocity.0,
+            obstacles,
+            ground_x: self
+                .q_ground
+                .iter()
+                .map(|(e, t)| (e, t.translation.x))
+                .collect(),
+            game_speed: self.game_speed.factor,
+            obstacles_data: *self.obstacles_data,
+            score: self.player_score.value,
+        })
+    }
+
+    /// Puts the world back as captured. The obstacles still around are moved back in place, the
+    /// ones spawned since are despawned and the ones passed since are respawned, returned along
+    /// with the entity they replace.
+    fn restore(&mut self, snapshot: &WorldSnapshot) -> Vec<(Entity, Entity)> {
+        if let Ok((mut t, mut v)) = self.q_player.get_single_mut() {
+            t.translation = snapshot.player_translation;
+            v.0 = snapshot.player_velocity;
+        }
+        let captured: HashSet<Entity> = snapshot.obstacles.iter().map(|(e, _)| *e).collect();
+        for (e, obstacle, ..) in self.q_obstacles.iter() {
+            if matches!(obstacle, Obstacle::GameOver | Obstacle::Opening) && !captured.contains(&e)
+            {
+                self.commands.entity(e).despawn();
+            }
+        }
+        for e in self.q_dropped.iter() {
+            self.commands.entity(e).despawn_recursive();
+        }
+        let pipe_hitbox = self.hitboxes.pipe.shrunk(self.hitbox_settings.forgiveness);
+        let mut respawned = Vec::new();
+        for &(e, obstacle) in snapshot.obstacles.iter() {
+            // moved in place, their colliders are kept
+            if let Ok((_, _, mut t, _, current_crossing)) = self.q_obstacles.get_mut(e) {
+                t.translation = obstacle.position().extend(t.translation.z);
+                if let (ObstacleSnapshot::Opening { crossing, .. }, Some(mut current_crossing)) =
+                    (obstacle, current_crossing)
+                {
+                    *current_crossing = crossing;
+                }
+                continue;
+            }
+            let respawned_entity = match obstacle {
+                ObstacleSnapshot::Pipe { position, flip_y } => spawn_obstacle_entity(
+                    &mut self.commands,
+                    self.game_assets.obstacle_image.clone(),
+                    if flip_y {
+                        pipe_hitbox.flipped_y()
+                    } else {
+                        pipe_hitbox.clone()
+                    },
+                    position,
+                    flip_y,
+                ),
+                ObstacleSnapshot::Opening { position, crossing } => {
+                    let opening = spawn_obstacle_opening_sensor_entity(
+                        &mut self.commands,
+                        position,
+                        self.obstacle_settings.opening_height,
+                    );
+                    self.commands.entity(opening).insert(crossing);
+                    opening
+                }
+            };
+            respawned.push((e, respawned_entity));
+        }
+        for &(e, x) in snapshot.ground_x.iter() {
+            if let Ok((_, mut t)) = self.q_ground.get_mut(e) {
+                t.translation.x = x;
+            }
+        }
+        self.game_speed.factor = snapshot.game_speed;
+        *self.obstacles_data = snapshot.obstacles_data;
+        // the score label pops on every change
+        if self.player_score.value != snapshot.score {
+            self.player_score.value = snapshot.score;
+        }
+        respawned
+    }
+}
+
+pub fn reset_rewind(
+    mut rewind: ResMut<Rewind>,
+    mut buffer: ResMut<RewindBuffer>,
+    mut time_scale: ResMut<TimeScale>,
+) {
+    *rewind = Rewind {
+        charges: REWIND_CHARGES,
+        ..Default::default()
+    };
+    buffer.0.clear();
+    time_scale.remove_factor(TIME_SCALE_REWIND);
+}
+
+/// Rewinds the world while the rewind key is held, records it otherwise.
+pub fn rewind_or_record_world(
+    mut rewind: ResMut<Rewind>,
+    mut buffer: ResMut<RewindBuffer>,
+    mut time_scale: ResMut<TimeScale>,
+    keyboard_input: Res<Input<KeyCode>>,
+    practice_mode: Res<PracticeMode>,
+    game_time: Res<GameTime>,
+    mut world: RewindableWorld,
+) {
+    let held = keyboard_input.pressed(REWIND_KEY);
+    if held && !rewind.active && buffer.0.len() > 1 {
+        if practice_mode.enabled {
+            rewind.active = true;
+        } else if rewind.charges > 0 {
+            rewind.charges -= 1;
+            rewind.active = true;
+        }
+        if rewind.active {
+            // the gameplay clock stands still while the world is rewound
+            time_scale.set_factor(TIME_SCALE_REWIND, 0.0);
+        }
+    }
+
+    if rewind.active {
+        if held && buffer.0.len() > 1 {
+            for _ in 0..REWIND_SPEED.min(buffer.0.len() - 1) {
+                buffer.0.pop_back();
+            }
+        } else {
+            rewind.active = false;
+            time_scale.remove_factor(TIME_SCALE_REWIND);
+        }
+        let respawned = buffer
+            .0
+            .back()
+            .map_or_else(Vec::new, |snapshot| world.restore(snapshot));
+        if !respawned.is_empty() {
+            for snapshot in buffer.0.iter_mut() {
+                snapshot.replace_obstacles(&respawned);
+            }
+        }
+        return;
+    }
+
+    if game_time.delta().is_zero() {
+        return;
+    }
+    if let Some(snapshot) = world.capture(game_time.elapsed_seconds()) {
+        buffer.0.push_back(snapshot);
+    }
+    while let (Some(oldest), Some(newest)) = (buffer.0.front(), buffer.0.back()) {
+        if newest.time - oldest.time <= REWIND_BUFFER_SECONDS {
+            break;
+        }
+        buffer.0.pop_front();
+    }
+}
+
+pub fn retry_before_death_on_input(
+    mut rewind: ResMut<Rewind>,
+    mut buffer: ResMut<RewindBuffer>,
+    mut ev_state: EventWriter<StateChangeRequest>,
+    keyboard_input: Res<Input<KeyCode>>,
+    practice_mode: Res<PracticeMode>,
+) {
+    if !practice_mode.enabled || !keyboard_input.just_pressed(REWIND_KEY) {
+        return;
+    }
+    let death_time = match buffer.0.back() {
+        Some(snapshot) => snapshot.time,
+        None => return,
+    };
+    while buffer.0.len() > 1
+        && buffer.0.back().map_or(false, |snapshot| {
+            snapshot.time > death_time - PRACTICE_RETRY_SECONDS
+        })
+    {
+        buffer.0.pop_back();
+    }
+    rewind.pending_retry = buffer.0.back().cloned();
+    ev_state.send(StateChangeRequest(AppState::InGame(InGameState::Playing)));
+}
+
+pub fn apply_pending_retry(
+    mut rewind: ResMut<Rewind>,
+    mut buffer: ResMut<RewindBuffer>,
+    mut run_stats: ResMut<RunStats>,
+    mut world: RewindableWorld,
+) {
+    if let Some(snapshot) = rewind.pending_retry.take() {
+        let respawned = world.restore(&snapshot);
+        for snapshot in buffer.0.iter_mut() {
+            snapshot.replace_obstacles(&respawned);
+        }
+        // the run goes on as if the death never happened
+        run_stats.death_cause = None;
+    }
+}
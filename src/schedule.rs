@@ -0,0 +1,71 @@
+use bevy::prelude::{App, CoreStage, Plugin, StageLabel, SystemLabel, SystemStage};
+#[cfg(feature = "rapier")]
+use bevy_rapier2d::prelude::PhysicsStages;
+
+/// Stages a gameplay frame goes through, in order: input, movement, physics, collision dispatch,
+/// scoring and UI. Everything a collision causes is then resolved within the frame the physics
+/// step reported it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, StageLabel)]
+pub enum GameStage {
+    Input,
+    Movement,
+    /// Steps the physics backend, or reads its results back when it runs in its own stages.
+    Physics,
+    CollisionDispatch,
+    Scoring,
+    Ui,
+}
+
+/// Systems of the [`GameStage::Scoring`] stage updating the score of the run, the ones reading
+/// it run after.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemLabel)]
+pub struct ScoreUpdate;
+
+/// Inserts the [`GameStage`]s, around the rapier stages when that backend is used and its plugin
+/// was added beforehand. It has to be added before any plugin adding systems to those stages.
+pub struct GameSchedulePlugin;
+
+impl Plugin for GameSchedulePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_stage_after(CoreStage::Update, GameStage::Input, SystemStage::parallel())
+            .add_stage_after(
+                GameStage::Input,
+                GameStage::Movement,
+                SystemStage::parallel(),
+            );
+        // the rapier stages are missing when its plugin is added afterwards or left out, the
+        // physics stage then simply follows the movement
+        #[cfg(feature = "rapier")]
+        let after_rapier = app
+            .schedule
+            .get_stage::<SystemStage>(PhysicsStages::Writeback)
+            .is_some();
+        #[cfg(not(feature = "rapier"))]
+        let after_rapier = false;
+        if after_rapier {
+            #[cfg(feature = "rapier")]
+            app.add_stage_after(
+                PhysicsStages::Writeback,
+                GameStage::Physics,
+                SystemStage::parallel(),
+            );
+        } else {
+            app.add_stage_after(
+                GameStage::Movement,
+                GameStage::Physics,
+                SystemStage::parallel(),
+            );
+        }
+        app.add_stage_after(
+            GameStage::Physics,
+            GameStage::CollisionDispatch,
+            SystemStage::parallel(),
+        )
+        .add_stage_after(
+            GameStage::CollisionDispatch,
+            GameStage::Scoring,
+            SystemStage::parallel(),
+        )
+        .add_stage_after(GameStage::Scoring, GameStage::Ui, SystemStage::parallel());
+    }
+}
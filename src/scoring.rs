@@ -0,0 +1,78 @@
+use bevy::prelude::{App, Plugin};
+use iyes_loopless::prelude::{AppLooplessStateExt, ConditionSet};
+
+use crate::{
+    achievements::{
+        evaluate_achievements, load_unlocked_achievements, reset_achievement_progress,
+        track_achievement_progress, AchievementProgress, AchievementUnlocked,
+    },
+    app_states::{AppState, InGameState},
+    game::not_practicing,
+    history::{load_run_history, record_finished_run},
+    medals::MedalThresholds,
+    persistence::Profile,
+    player::{handle_game_event_player_passed_opening, reset_player_score, PlayerScore},
+    schedule::{GameStage, ScoreUpdate},
+    stats::{
+        load_lifetime_stats, record_lifetime_stats, reset_run_stats, track_death_cause,
+        track_run_stats, RunStats,
+    },
+    transitions::no_screen_transition,
+};
+
+/// The score of the run and everything derived from it: medals, run history, achievements and
+/// statistics, persisted under the current [`Profile`].
+pub struct ScoringPlugin;
+
+impl Plugin for ScoringPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Profile>()
+            .init_resource::<PlayerScore>()
+            .init_resource::<MedalThresholds>()
+            .init_resource::<AchievementProgress>()
+            .init_resource::<RunStats>()
+            .add_event::<AchievementUnlocked>()
+            .add_startup_system(load_run_history)
+            .add_startup_system(load_unlocked_achievements)
+            .add_startup_system(load_lifetime_stats)
+            .add_enter_system_set(
+                AppState::InGame(InGameState::ReadyToStart),
+                ConditionSet::new()
+                    .with_system(reset_player_score)
+                    .with_system(reset_achievement_progress)
+                    .with_system(reset_run_stats)
+                    .into(),
+            )
+            .add_system_set_to_stage(
+                GameStage::Scoring,
+                ConditionSet::new()
+                    .run_in_state(AppState::InGame(InGameState::Playing))
+                    .run_if(no_screen_transition)
+                    .label(ScoreUpdate)
+                    .with_system(handle_game_event_player_passed_opening)
+                    .with_system(track_achievement_progress)
+                    .with_system(track_run_stats)
+                    .with_system(track_death_cause)
+                    .into(),
+            )
+            .add_system_set_to_stage(
+                GameStage::Scoring,
+                ConditionSet::new()
+                    .run_in_state(AppState::InGame(InGameState::Playing))
+                    .run_if(no_screen_transition)
+                    // practice runs unlock nothing, like they record nothing
+                    .run_if(not_practicing)
+                    .after(ScoreUpdate)
+                    .with_system(evaluate_achievements)
+                    .into(),
+            )
+            .add_exit_system_set(
+                AppState::InGame(InGameState::Playing),
+                ConditionSet::new()
+                    .run_if(not_practicing)
+                    .with_system(record_finished_run)
+                    .with_system(record_lifetime_stats)
+                    .into(),
+            );
+    }
+}
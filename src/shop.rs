@@ -0,0 +1,335 @@
+use bevy::{
+    prelude::{
+        App, BuildChildren, ClearColor, Color, Commands, Component, DespawnRecursiveExt, Entity,
+        EventWriter, Input, KeyCode, Plugin, Query, Res, ResMut, Resource, SpatialBundle,
+        Transform, Vec3, With,
+    },
+    sprite::SpriteBundle,
+};
+use iyes_loopless::prelude::{AppLooplessStateExt, IntoConditionalSystem};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_states::{AppState, InGameState, MenuState},
+    assets::GameAssets,
+    characters::SelectedCharacter,
+    coins::Wallet,
+    particles::ParticleEmitter,
+    persistence::{self, Profile},
+    player::Player,
+    transitions::{no_screen_transition, StateChangeRequest},
+    ui::{free_toast_slot, spawn_toast, Toast},
+    world::SKY_COLOR,
+};
+
+const SHOP_DATA: &str = include_str!("../assets/sprites/shop.ron");
+const LOADOUT_KEY: &str = "loadout";
+
+const TRAIL_RATE: f32 = 30.0;
+const TRAIL_OFFSET: Vec3 = Vec3::new(-8.0, 0.0, 0.0);
+// behind the ground, the main camera draws from 0.1 below zero
+const BACKGROUND_Z: f32 = -0.05;
+
+/// Characters, trails and backgrounds bought with coins, and the ones equipped.
+pub struct ShopPlugin;
+
+impl Plugin for ShopPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ShopCatalog>()
+            .init_resource::<Profile>()
+            .init_resource::<Loadout>()
+            .add_startup_system(load_loadout)
+            .add_enter_system(
+                AppState::InGame(InGameState::Initialization),
+                apply_background,
+            )
+            .add_enter_system(
+                AppState::InGame(InGameState::ReadyToStart),
+                apply_background,
+            )
+            .add_enter_system(AppState::InGame(InGameState::Playing), spawn_player_trail)
+            .add_exit_system(AppState::InGame(InGameState::Playing), despawn_player_trail)
+            // MENU - SHOP
+            .add_enter_system(AppState::Menu(MenuState::Shop), start_shop)
+            .add_system(
+                browse_shop_on_input
+                    .run_in_state(AppState::Menu(MenuState::Shop))
+                    .run_if(no_screen_transition),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub enum ShopItemKind {
+    /// Unlocks the roster character with the id of the item.
+    Character,
+    Trail {
+        effect: String,
+    },
+    Background {
+        color: String,
+        image: bool,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ShopItem {
+    pub id: String,
+    pub name: String,
+    pub price: u32,
+    pub kind: ShopItemKind,
+}
+
+#[derive(Deserialize)]
+struct ShopData {
+    items: Vec<ShopItem>,
+}
+
+/// The items on sale, defined in `sprites/shop.ron`.
+#[derive(Resource, Debug, Clone)]
+pub struct ShopCatalog {
+    items: Vec<ShopItem>,
+}
+
+impl Default for ShopCatalog {
+    fn default() -> Self {
+        let data: ShopData = ron::from_str(SHOP_DATA).expect("the shop data file is invalid");
+        Self { items: data.items }
+    }
+}
+
+impl ShopCatalog {
+    pub fn items(&self) -> &[ShopItem] {
+        &self.items
+    }
+
+    pub fn get(&self, id: &str) -> Option<&ShopItem> {
+        self.items.iter().find(|item| item.id == id)
+    }
+
+    /// Price of the item when it is on sale and not bought yet.
+    pub fn locked_price(&self, id: &str, wallet: &Wallet) -> Option<u32> {
+        self.get(id)
+            .filter(|item| item.price > 0 && !wallet.owns(&item.id))
+            .map(|item| item.price)
+    }
+}
+
+/// The trail and background equipped, persisted under the current [`Profile`].
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Loadout {
+    pub trail: Option<String>,
+    pub background: Option<String>,
+}
+
+/// Catalog index highlighted in the shop.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct ShopCursor(pub usize);
+
+#[derive(Component)]
+pub struct Background;
+
+/// Child of the [`Player`] leaving the equipped trail behind it.
+#[derive(Component)]
+pub struct PlayerTrail;
+
+pub fn load_loadout(mut commands: Commands, profile: Res<Profile>) {
+    commands.insert_resource(persistence::load_or_default::<Loadout>(
+        &profile,
+        LOADOUT_KEY,
+    ));
+}
+
+pub fn start_shop(mut commands: Commands) {
+    commands.insert_resource(ShopCursor(0));
+}
+
+pub fn browse_shop_on_input(
+    mut commands: Commands,
+    mut ev_state: EventWriter<StateChangeRequest>,
+    mut cursor: ResMut<ShopCursor>,
+    mut wallet: ResMut<Wallet>,
+    mut loadout: ResMut<Loadout>,
+    mut selected_character: ResMut<SelectedCharacter>,
+    catalog: Res<ShopCatalog>,
+    profile: Res<Profile>,
+    game_assets: Res<GameAssets>,
+    keyboard_input: Res<Input<KeyCode>>,
+    q_toasts: Query<&Toast>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        ev_state.send(StateChangeRequest(AppState::InGame(
+            InGameState::ReadyToStart,
+        )));
+        return;
+    }
+    let count = catalog.items().len();
+    if count == 0 {
+        return;
+    }
+    if keyboard_input.any_just_pressed([KeyCode::Up, KeyCode::W]) {
+        cursor.0 = (cursor.0 + count - 1) % count;
+    }
+    if keyboard_input.any_just_pressed([KeyCode::Down, KeyCode::S]) {
+        cursor.0 = (cursor.0 + 1) % count;
+    }
+    if !keyboard_input.any_just_pressed([KeyCode::Space, KeyCode::Return]) {
+        return;
+    }
+
+    let item = &catalog.items()[cursor.0];
+    let message = if wallet.owns(&item.id) || item.price == 0 {
+        equip(item, &mut loadout, &mut selected_character, &profile)
+    } else if wallet.buy(&item.id, item.price) {
+        wallet.save(&profile);
+        equip(item, &mut loadout, &mut selected_character, &profile);
+        format!("Bought {}", item.name)
+    } else {
+        format!("{} more coins needed", item.price - wallet.coins)
+    };
+    spawn_toast(
+        &mut commands,
+        game_assets.font.clone(),
+        "Shop",
+        &message,
+        free_toast_slot(&q_toasts),
+    );
+}
+
+/// Equips the item, or takes it off when it already was, and tells what changed.
+fn equip(
+    item: &ShopItem,
+    loadout: &mut Loadout,
+    selected_character: &mut SelectedCharacter,
+    profile: &Profile,
+) -> String {
+    let slot = match item.kind {
+        ShopItemKind::Character => {
+            selected_character.id = item.id.clone();
+            selected_character.save(profile);
+            return format!("Flying as {}", item.name);
+        }
+        ShopItemKind::Trail { .. } => &mut loadout.trail,
+        ShopItemKind::Background { .. } => &mut loadout.background,
+    };
+    let message = if slot.as_deref() == Some(item.id.as_str()) {
+        *slot = None;
+        format!("{} taken off", item.name)
+    } else {
+        *slot = Some(item.id.clone());
+        format!("{} equipped", item.name)
+    };
+    persistence::save(profile, LOADOUT_KEY, &*loadout);
+    message
+}
+
+pub fn apply_background(
+    mut commands: Commands,
+    mut clear_color: ResMut<ClearColor>,
+    loadout: Res<Loadout>,
+    catalog: Res<ShopCatalog>,
+    game_assets: Res<GameAssets>,
+    q_background: Query<Entity, With<Background>>,
+) {
+    for e in q_background.iter() {
+        commands.entity(e).despawn();
+    }
+    let equipped = loadout
+        .background
+        .as_deref()
+        .and_then(|id| catalog.get(id))
+        .map(|item| &item.kind);
+    let (color, image) = match equipped {
+        Some(ShopItemKind::Background { color, image }) => (color.as_str(), *image),
+        _ => (SKY_COLOR, false),
+    };
+    clear_color.0 = Color::hex(color).expect("a background color is not a valid hex color");
+    if image {
+        commands.spawn((
+            SpriteBundle {
+                texture: game_assets.background_image.clone(),
+                transform: Transform::from_xyz(0.0, 0.0, BACKGROUND_Z),
+                ..Default::default()
+            },
+            Background,
+        ));
+    }
+}
+
+pub fn spawn_player_trail(
+    mut commands: Commands,
+    loadout: Res<Loadout>,
+    catalog: Res<ShopCatalog>,
+    q_player: Query<Entity, With<Player>>,
+) {
+    let equipped = loadout
+        .trail
+        .as_deref()
+        .and_then(|id| catalog.get(id))
+        .map(|item| &item.kind);
+    if let (Some(ShopItemKind::Trail { effect }), Ok(player)) = (equipped, q_player.get_single()) {
+        commands.entity(player).with_children(|parent| {
+            parent.spawn((
+                SpatialBundle::from_transform(Transform::from_translation(TRAIL_OFFSET)),
+                ParticleEmitter::continuous(effect, TRAIL_RATE),
+                PlayerTrail,
+            ));
+        });
+    }
+}
+
+pub fn despawn_player_trail(mut commands: Commands, q_trail: Query<Entity, With<PlayerTrail>>) {
+    for e in q_trail.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::{characters::CharacterRoster, particles::ParticleEffects};
+
+    #[test]
+    fn item_ids_are_unique() {
+        let catalog = ShopCatalog::default();
+        let mut ids = HashSet::new();
+        for item in catalog.items() {
+            assert!(ids.insert(&item.id), "{} is on sale twice", item.id);
+        }
+    }
+
+    #[test]
+    fn items_refer_to_existing_content() {
+        let roster = CharacterRoster::default();
+        let effects = ParticleEffects::default();
+        for item in ShopCatalog::default().items() {
+            match &item.kind {
+                ShopItemKind::Character => assert!(
+                    roster.index_of(&item.id).is_some(),
+                    "{} is not a character of the roster",
+                    item.id
+                ),
+                ShopItemKind::Trail { effect } => assert!(
+                    effects.get(effect).is_some(),
+                    "{} leaves the unknown {} effect",
+                    item.id,
+                    effect
+                ),
+                ShopItemKind::Background { color, .. } => assert!(
+                    Color::hex(color).is_ok(),
+                    "{} has the invalid color {}",
+                    item.id,
+                    color
+                ),
+            }
+        }
+    }
+
+    #[test]
+    fn sky_color_is_valid() {
+        assert!(Color::hex(SKY_COLOR).is_ok());
+    }
+}
@@ -0,0 +1,139 @@
+use bevy::prelude::{
+    Commands, EventReader, Query, Res, ResMut, Resource, Transform, With, Without,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    consts::BASE_MOVE_SPEED,
+    events::{DeathCause, GameEvent},
+    game::{GameSpeed, GameTime},
+    obstacles::{opening_clearance, Obstacle, ObstacleSettings, ObstaclesData},
+    persistence::{self, Profile},
+    physics::PhysicsBody,
+    player::{Player, PlayerScore},
+};
+
+const LIFETIME_STATS_KEY: &str = "lifetime_stats";
+
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RunStats {
+    pub flaps: u32,
+    pub airtime: f32,
+    pub distance: f32,
+    pub max_speed: f32,
+    pub closest_near_miss: Option<f32>,
+    pub death_cause: Option<DeathCause>,
+}
+
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LifetimeStats {
+    pub runs: u32,
+    pub total_score: u32,
+    pub best_score: u16,
+    pub flaps: u64,
+    pub airtime: f32,
+    pub distance: f32,
+    pub max_speed: f32,
+    pub closest_near_miss: Option<f32>,
+    pub deaths_top_bound: u32,
+    pub deaths_ground: u32,
+    pub deaths_pipe: u32,
+    #[serde(default)]
+    pub deaths_hazard: u32,
+}
+
+impl LifetimeStats {
+    fn add_run(&mut self, run: &RunStats, score: u16) {
+        self.runs += 1;
+        self.total_score += score as u32;
+        self.best_score = self.best_score.max(score);
+        self.flaps += run.flaps as u64;
+        self.airtime += run.airtime;
+        self.distance += run.distance;
+        self.max_speed = self.max_speed.max(run.max_speed);
+        self.closest_near_miss = min_option(self.closest_near_miss, run.closest_near_miss);
+        match run.death_cause {
+            Some(DeathCause::TopBound) => self.deaths_top_bound += 1,
+            Some(DeathCause::Ground) => self.deaths_ground += 1,
+            Some(DeathCause::Pipe) => self.deaths_pipe += 1,
+            Some(DeathCause::Bird | DeathCause::Rock) => self.deaths_hazard += 1,
+            None => {}
+        }
+    }
+}
+
+pub fn load_lifetime_stats(mut commands: Commands, profile: Res<Profile>) {
+    commands.insert_resource(persistence::load_or_default::<LifetimeStats>(
+        &profile,
+        LIFETIME_STATS_KEY,
+    ));
+}
+
+pub fn reset_run_stats(mut run_stats: ResMut<RunStats>) {
+    *run_stats = RunStats::default();
+}
+
+pub fn track_run_stats(
+    mut run_stats: ResMut<RunStats>,
+    obstacles_data: Res<ObstaclesData>,
+    obstacle_settings: Res<ObstacleSettings>,
+    game_speed: Res<GameSpeed>,
+    q_player: Query<(&Transform, &PhysicsBody), With<Player>>,
+    q_obstacles: Query<(&Transform, &Obstacle), Without<Player>>,
+    mut ev_game: EventReader<GameEvent>,
+    game_time: Res<GameTime>,
+) {
+    run_stats.flaps += ev_game
+        .iter()
+        .filter(|ev| matches!(ev, GameEvent::PlayerFlapped { .. }))
+        .count() as u32;
+    run_stats.airtime += game_time.delta_seconds();
+    run_stats.distance = obstacles_data.traveled_distance();
+    run_stats.max_speed = run_stats.max_speed.max(BASE_MOVE_SPEED * game_speed.factor);
+
+    if let Ok((player_transform, player_body)) = q_player.get_single() {
+        let near_miss = q_obstacles
+            .iter()
+            .filter(|(_, &o)| o == Obstacle::Opening)
+            .filter_map(|(t, _)| {
+                opening_clearance(
+                    t.translation,
+                    obstacle_settings.opening_height,
+                    player_transform.translation,
+                    &player_body.hitbox,
+                )
+            })
+            .filter(|&clearance| clearance >= 0.0)
+            .reduce(f32::min);
+        run_stats.closest_near_miss = min_option(run_stats.closest_near_miss, near_miss);
+    }
+}
+
+pub fn track_death_cause(mut run_stats: ResMut<RunStats>, mut ev_game: EventReader<GameEvent>) {
+    for ev in ev_game.iter() {
+        if let GameEvent::PlayerHitGameOverCollider { cause, .. } = ev {
+            // a single frame can touch several colliders, the first one reported wins
+            if run_stats.death_cause.is_none() {
+                run_stats.death_cause = Some(*cause);
+            }
+        }
+    }
+}
+
+pub fn record_lifetime_stats(
+    mut lifetime_stats: ResMut<LifetimeStats>,
+    run_stats: Res<RunStats>,
+    player_score: Res<PlayerScore>,
+    profile: Res<Profile>,
+) {
+    lifetime_stats.add_run(&run_stats, player_score.value);
+    persistence::save(&profile, LIFETIME_STATS_KEY, &*lifetime_stats);
+}
+
+fn min_option(a: Option<f32>, b: Option<f32>) -> Option<f32> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
@@ -0,0 +1,171 @@
+use bevy::{
+    prelude::{
+        Color, Commands, Component, DespawnRecursiveExt, Entity, EventReader, Query, Res, ResMut,
+        Resource, Transform, Vec2, Vec3, With,
+    },
+    sprite::{Anchor, Sprite, SpriteBundle},
+    time::{Time, Timer, TimerMode},
+};
+use iyes_loopless::state::{CurrentState, NextState};
+
+use crate::{
+    app_states::{AppState, InGameState, LaunchingState},
+    consts::{GAME_HEIGHT, GAME_WIDTH},
+    tweening::Ease,
+};
+
+const TRANSITION_DURATION: f32 = 0.6;
+const TRANSITION_OVERLAY_Z: f32 = 900.0;
+const TRANSITION_OVERLAY_COLOR: Color = Color::rgb(0.263, 0.29, 0.373);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransitionKind {
+    Fade,
+    Wipe,
+}
+
+/// Asks for a state change, which is applied at the midpoint of the matching screen transition.
+#[derive(Debug, Clone)]
+pub struct StateChangeRequest(pub AppState);
+
+#[derive(Resource, Default)]
+pub struct ScreenTransition {
+    active: Option<ActiveTransition>,
+}
+
+impl ScreenTransition {
+    pub fn is_active(&self) -> bool {
+        self.active.is_some()
+    }
+}
+
+struct ActiveTransition {
+    kind: TransitionKind,
+    next_state: AppState,
+    timer: Timer,
+    state_switched: bool,
+}
+
+#[derive(Component)]
+pub struct TransitionOverlay;
+
+pub fn transition_kind(from: &AppState, to: &AppState) -> Option<TransitionKind> {
+    match (from, to) {
+        (AppState::Launching(LaunchingState::Loading), _) => None,
+        // gameplay keeps its pace when a run starts or ends
+        (AppState::InGame(InGameState::ReadyToStart), AppState::InGame(InGameState::Playing))
+        | (AppState::InGame(InGameState::Playing), AppState::InGame(InGameState::GameOver)) => None,
+        (AppState::InGame(InGameState::GameOver), AppState::InGame(InGameState::ReadyToStart)) => {
+            Some(TransitionKind::Wipe)
+        }
+        _ => Some(TransitionKind::Fade),
+    }
+}
+
+pub fn no_screen_transition(transition: Res<ScreenTransition>) -> bool {
+    !transition.is_active()
+}
+
+pub fn start_requested_transitions(
+    mut commands: Commands,
+    mut ev_requests: EventReader<StateChangeRequest>,
+    current_state: Res<CurrentState<AppState>>,
+    mut transition: ResMut<ScreenTransition>,
+) {
+    for StateChangeRequest(next_state) in ev_requests.iter() {
+        // a running transition already owns the next state change
+        if transition.is_active() || &current_state.0 == next_state {
+            continue;
+        }
+        match transition_kind(&current_state.0, next_state) {
+            None => commands.insert_resource(NextState(next_state.clone())),
+            Some(kind) => {
+                commands.spawn((
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color: overlay_color(0.0),
+                            custom_size: Some(Vec2::ZERO),
+                            ..Default::default()
+                        },
+                        transform: Transform::from_translation(Vec3::new(
+                            0.0,
+                            0.0,
+                            TRANSITION_OVERLAY_Z,
+                        )),
+                        ..Default::default()
+                    },
+                    TransitionOverlay,
+                ));
+                transition.active = Some(ActiveTransition {
+                    kind,
+                    next_state: next_state.clone(),
+                    timer: Timer::from_seconds(TRANSITION_DURATION, TimerMode::Once),
+                    state_switched: false,
+                });
+            }
+        }
+    }
+}
+
+pub fn play_screen_transition(
+    mut commands: Commands,
+    mut transition: ResMut<ScreenTransition>,
+    mut q_overlay: Query<(Entity, &mut Sprite, &mut Transform), With<TransitionOverlay>>,
+    time: Res<Time>,
+) {
+    let finished = if let Some(active) = transition.active.as_mut() {
+        active.timer.tick(time.delta());
+        let progress = active.timer.percent();
+        let covering = progress < 0.5;
+        let coverage = if covering {
+            Ease::QuadInOut.apply(progress * 2.0)
+        } else {
+            Ease::QuadInOut.apply(2.0 - progress * 2.0)
+        };
+
+        if !covering && !active.state_switched {
+            commands.insert_resource(NextState(active.next_state.clone()));
+            active.state_switched = true;
+        }
+
+        for (_, mut sprite, mut t) in q_overlay.iter_mut() {
+            match active.kind {
+                TransitionKind::Fade => {
+                    sprite.color = overlay_color(coverage);
+                    sprite.custom_size = Some(Vec2::new(GAME_WIDTH, GAME_HEIGHT));
+                    sprite.anchor = Anchor::Center;
+                    t.translation.x = 0.0;
+                }
+                TransitionKind::Wipe => {
+                    // grows from the left edge, then shrinks towards the right edge
+                    sprite.color = TRANSITION_OVERLAY_COLOR;
+                    sprite.custom_size = Some(Vec2::new(GAME_WIDTH * coverage, GAME_HEIGHT));
+                    if covering {
+                        sprite.anchor = Anchor::CenterLeft;
+                        t.translation.x = -GAME_WIDTH / 2.0;
+                    } else {
+                        sprite.anchor = Anchor::CenterRight;
+                        t.translation.x = GAME_WIDTH / 2.0;
+                    }
+                }
+            }
+        }
+
+        active.timer.finished()
+    } else {
+        false
+    };
+
+    if finished {
+        transition.active = None;
+        for (e, _, _) in q_overlay.iter() {
+            commands.entity(e).despawn_recursive();
+        }
+    }
+}
+
+fn overlay_color(alpha: f32) -> Color {
+    let mut color = TRANSITION_OVERLAY_COLOR;
+    color.set_a(alpha);
+    color
+}
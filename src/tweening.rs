@@ -0,0 +1,245 @@
+use std::f32::consts::PI;
+
+use bevy::{
+    prelude::{
+        Color, Commands, Component, DespawnRecursiveExt, Entity, Mut, Query, Res, Transform, Vec3,
+    },
+    sprite::{Sprite, TextureAtlasSprite},
+    text::Text,
+    time::{Time, Timer, TimerMode},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ease {
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicOut,
+    SineInOut,
+    BackOut,
+}
+
+impl Ease {
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Ease::Linear => t,
+            Ease::QuadIn => t * t,
+            Ease::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Ease::QuadInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+                }
+            }
+            Ease::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Ease::SineInOut => -((PI * t).cos() - 1.0) / 2.0,
+            Ease::BackOut => {
+                let c1 = 1.70158;
+                let c3 = c1 + 1.0;
+                1.0 + c3 * (t - 1.0).powi(3) + c1 * (t - 1.0).powi(2)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum TweenTarget {
+    Translation {
+        start: Vec3,
+        end: Vec3,
+    },
+    /// Moves the entity relatively, so it composes with other systems writing the translation.
+    Offset {
+        by: Vec3,
+    },
+    Scale {
+        start: Vec3,
+        end: Vec3,
+    },
+    Color {
+        start: Color,
+        end: Color,
+    },
+    Alpha {
+        start: f32,
+        end: f32,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TweenMode {
+    Once,
+    Loop,
+    /// Plays forward then backward, and completes when back at the start.
+    Yoyo,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TweenCompletion {
+    Keep,
+    Remove,
+    Despawn,
+}
+
+#[derive(Component, Debug, Clone)]
+pub struct Tween {
+    targets: Vec<TweenTarget>,
+    ease: Ease,
+    mode: TweenMode,
+    completion: TweenCompletion,
+    timer: Timer,
+    played_backward: bool,
+    previous_value: f32,
+}
+
+impl Tween {
+    pub fn new(duration: f32, ease: Ease) -> Self {
+        Self {
+            targets: Vec::new(),
+            ease,
+            mode: TweenMode::Once,
+            completion: TweenCompletion::Remove,
+            timer: Timer::from_seconds(duration, TimerMode::Once),
+            played_backward: false,
+            previous_value: 0.0,
+        }
+    }
+
+    pub fn with(mut self, target: TweenTarget) -> Self {
+        self.targets.push(target);
+        self
+    }
+
+    pub fn with_mode(mut self, mode: TweenMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn on_completion(mut self, completion: TweenCompletion) -> Self {
+        self.completion = completion;
+        self
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.timer.finished() && (self.mode != TweenMode::Yoyo || self.played_backward)
+    }
+
+    fn progress(&self) -> f32 {
+        if self.played_backward {
+            1.0 - self.timer.percent()
+        } else {
+            self.timer.percent()
+        }
+    }
+}
+
+pub fn animate_tweens(
+    mut commands: Commands,
+    mut q_tweens: Query<(
+        Entity,
+        &mut Tween,
+        Option<&mut Transform>,
+        Option<&mut Sprite>,
+        Option<&mut TextureAtlasSprite>,
+        Option<&mut Text>,
+    )>,
+    time: Res<Time>,
+) {
+    for (e, mut tween, mut transform, mut sprite, mut atlas_sprite, mut text) in q_tweens.iter_mut()
+    {
+        tween.timer.tick(time.delta());
+        let value = tween.ease.apply(tween.progress());
+        let previous_value = tween.previous_value;
+        for target in tween.targets.iter() {
+            match *target {
+                TweenTarget::Translation { start, end } => {
+                    if let Some(t) = transform.as_mut() {
+                        t.translation = start.lerp(end, value);
+                    }
+                }
+                TweenTarget::Offset { by } => {
+                    if let Some(t) = transform.as_mut() {
+                        t.translation += by * (value - previous_value);
+                    }
+                }
+                TweenTarget::Scale { start, end } => {
+                    if let Some(t) = transform.as_mut() {
+                        t.scale = start.lerp(end, value);
+                    }
+                }
+                TweenTarget::Color { start, end } => {
+                    let color = lerp_color(start, end, value);
+                    set_color(&mut sprite, &mut atlas_sprite, &mut text, |c| *c = color);
+                }
+                TweenTarget::Alpha { start, end } => {
+                    let alpha = start + (end - start) * value;
+                    set_color(&mut sprite, &mut atlas_sprite, &mut text, |c| {
+                        c.set_a(alpha);
+                    });
+                }
+            }
+        }
+        tween.previous_value = value;
+
+        if tween.timer.finished() {
+            match tween.mode {
+                TweenMode::Once => {}
+                TweenMode::Loop => {
+                    tween.timer.reset();
+                    tween.previous_value = 0.0;
+                }
+                TweenMode::Yoyo => {
+                    if !tween.played_backward {
+                        tween.played_backward = true;
+                        tween.timer.reset();
+                    }
+                }
+            }
+        }
+
+        if tween.is_finished() {
+            match tween.completion {
+                TweenCompletion::Keep => {}
+                TweenCompletion::Remove => {
+                    commands.entity(e).remove::<Tween>();
+                }
+                TweenCompletion::Despawn => {
+                    commands.entity(e).despawn_recursive();
+                }
+            }
+        }
+    }
+}
+
+pub fn lerp_color(start: Color, end: Color, t: f32) -> Color {
+    let [sr, sg, sb, sa] = start.as_rgba_f32();
+    let [er, eg, eb, ea] = end.as_rgba_f32();
+    Color::rgba(
+        sr + (er - sr) * t,
+        sg + (eg - sg) * t,
+        sb + (eb - sb) * t,
+        sa + (ea - sa) * t,
+    )
+}
+
+fn set_color(
+    sprite: &mut Option<Mut<Sprite>>,
+    atlas_sprite: &mut Option<Mut<TextureAtlasSprite>>,
+    text: &mut Option<Mut<Text>>,
+    apply: impl Fn(&mut Color),
+) {
+    if let Some(s) = sprite.as_mut() {
+        apply(&mut s.color);
+    }
+    if let Some(s) = atlas_sprite.as_mut() {
+        apply(&mut s.color);
+    }
+    if let Some(t) = text.as_mut() {
+        for section in t.sections.iter_mut() {
+            apply(&mut section.style.color);
+        }
+    }
+}
@@ -1,33 +1,270 @@
 use bevy::{
     prelude::{
-        Color, Commands, Component, DespawnRecursiveExt, Entity, Query, Res, Transform, Vec3,
+        Added, App, BuildChildren, ChildBuilder, Color, Commands, Component, DespawnRecursiveExt,
+        Entity, EventReader, Handle, Plugin, Query, Res, SpatialBundle, Transform, Vec2, Vec3,
+        With, Without,
     },
-    text::{Text, Text2dBundle, TextAlignment, TextStyle},
+    sprite::{Anchor, Sprite, SpriteBundle, SpriteSheetBundle, TextureAtlas, TextureAtlasSprite},
+    text::{Text, Text2dBundle, TextAlignment, TextSection, TextStyle},
+    time::{Time, Timer, TimerMode},
 };
+use iyes_loopless::prelude::{AppLooplessStateExt, ConditionSet};
 
-use crate::{assets::GameAssets, consts::GAME_HEIGHT, player::PlayerScore};
+use crate::{
+    achievements::announce_unlocked_achievements,
+    animations::{animate_sprite, Animator, CLIP_IDLE},
+    app_states::{AppState, InGameState, MenuState},
+    assets::GameAssets,
+    camera::Readable,
+    characters::{CharacterRoster, CharacterSelectCursor, SelectedCharacter},
+    coins::{RunCoins, Wallet},
+    consts::{GAME_HEIGHT, GAME_WIDTH},
+    events::GameEvent,
+    game::HorizontalMove,
+    history::RunHistory,
+    mutators::{Mutator, MutatorCursor, Mutators},
+    player::PlayerScore,
+    powerups::{ActivePowerUps, PowerUpRegistry},
+    schedule::GameStage,
+    shop::{Loadout, ShopCatalog, ShopCursor, ShopItemKind},
+    stats::LifetimeStats,
+    transitions::no_screen_transition,
+    tweening::{animate_tweens, Ease, Tween, TweenCompletion, TweenMode, TweenTarget},
+};
+
+const READY_HINTS: &str = "S stats  C character  B shop  X mutators  P practice  M motion";
+
+const PANEL_WIDTH: f32 = 320.0;
+const PANEL_LINE_HEIGHT: f32 = 14.0;
+
+const DIGIT_ADVANCE: f32 = 12.0;
+const DIGIT_PLUS_INDEX: usize = 10;
+const DIGITS_Z: f32 = 10.0;
+
+const SCORE_POP_DURATION: f32 = 0.18;
+const SCORE_POP_SCALE: f32 = 0.4;
+
+const SCORE_POPUP_DURATION: f32 = 0.8;
+const SCORE_POPUP_RISE: f32 = 28.0;
+
+const RUN_COINS_MARGIN: f32 = 8.0;
+
+const POWER_UP_TIMERS_MARGIN: f32 = 8.0;
+const POWER_UP_TIMER_ROW_HEIGHT: f32 = 12.0;
+const POWER_UP_TIMER_BAR_X: f32 = 60.0;
+const POWER_UP_TIMER_BAR_SIZE: Vec2 = Vec2::new(48.0, 4.0);
+
+const SUMMARY_Z: f32 = 50.0;
+const SUMMARY_SIZE: Vec2 = Vec2::new(216.0, 126.0);
+const SUMMARY_ENTER_DURATION: f32 = 0.45;
+const SUMMARY_PANEL_COLOR: Color = Color::rgba(0.263, 0.29, 0.373, 0.92);
+
+const CHARACTER_PREVIEW_SPACING: f32 = 60.0;
+const CHARACTER_PREVIEW_SCALE: f32 = 2.0;
+const CHARACTER_PREVIEW_SELECTED_SCALE: f32 = 2.5;
+const CHARACTER_PREVIEW_DIMMED_ALPHA: f32 = 0.45;
+const CHARACTER_SELECT_HEIGHT: f32 = 150.0;
+const CHARACTER_SELECT_HINTS: &str = "Left Right choose  Space confirm  Esc back";
+const CHARACTER_LOCKED_COLOR: Color = Color::rgb(0.2, 0.2, 0.25);
+
+const SHOP_HINTS: &str = "Up Down choose  Space buy or equip  Esc back";
+
+const MUTATORS_HINTS: &str = "Up Down choose  Space toggle  Esc back";
+const MUTATORS_HEIGHT: f32 = 252.0;
+
+const TOAST_Z: f32 = 800.0;
+const TOAST_SIZE: Vec2 = Vec2::new(196.0, 32.0);
+const TOAST_MARGIN: f32 = 6.0;
+const TOAST_DURATION: f32 = 2.6;
+const TOAST_SLIDE_DURATION: f32 = 0.3;
+
+/// Labels, score, panels and toasts, plus the tweens animating them.
+pub struct UiPlugin;
+
+impl Plugin for UiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(animate_tweens)
+            .add_system_to_stage(GameStage::Ui, announce_unlocked_achievements)
+            .add_system_to_stage(GameStage::Ui, animate_sprite)
+            .add_system(expire_toasts)
+            .add_system_to_stage(GameStage::Ui, mark_readable_ui_elements)
+            // IN GAME - READY TO START
+            .add_enter_system(
+                AppState::InGame(InGameState::ReadyToStart),
+                spawn_game_ready_label,
+            )
+            .add_exit_system(
+                AppState::InGame(InGameState::ReadyToStart),
+                despawn_game_ready_label,
+            )
+            // IN GAME - PLAYING
+            .add_enter_system_set(
+                AppState::InGame(InGameState::Playing),
+                ConditionSet::new()
+                    .with_system(spawn_game_score)
+                    .with_system(spawn_run_coins_label)
+                    .with_system(spawn_power_up_timers)
+                    .into(),
+            )
+            .add_system_set_to_stage(
+                GameStage::Ui,
+                ConditionSet::new()
+                    .run_in_state(AppState::InGame(InGameState::Playing))
+                    .run_if(no_screen_transition)
+                    .with_system(update_player_score_label)
+                    .with_system(spawn_score_popup)
+                    .with_system(update_run_coins_label)
+                    .with_system(update_power_up_timers)
+                    .into(),
+            )
+            .add_exit_system_set(
+                AppState::InGame(InGameState::Playing),
+                ConditionSet::new()
+                    .with_system(despawn_game_score)
+                    .with_system(despawn_score_popups)
+                    .with_system(despawn_run_coins_label)
+                    .with_system(despawn_power_up_timers)
+                    .into(),
+            )
+            // IN GAME - GAME OVER
+            .add_enter_system(
+                AppState::InGame(InGameState::GameOver),
+                spawn_game_over_summary,
+            )
+            .add_exit_system(
+                AppState::InGame(InGameState::GameOver),
+                despawn_game_over_summary,
+            )
+            // MENU - STATS
+            .add_enter_system(AppState::Menu(MenuState::Stats), spawn_stats_screen)
+            .add_exit_system(AppState::Menu(MenuState::Stats), despawn_stats_screen)
+            // MENU - CHARACTER SELECT
+            .add_enter_system(
+                AppState::Menu(MenuState::CharacterSelect),
+                spawn_character_select_screen,
+            )
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(AppState::Menu(MenuState::CharacterSelect))
+                    .with_system(update_character_select_screen)
+                    .into(),
+            )
+            .add_exit_system(
+                AppState::Menu(MenuState::CharacterSelect),
+                despawn_character_select_screen,
+            )
+            // MENU - SHOP
+            .add_enter_system(AppState::Menu(MenuState::Shop), spawn_shop_screen)
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(AppState::Menu(MenuState::Shop))
+                    .with_system(update_shop_screen)
+                    .into(),
+            )
+            .add_exit_system(AppState::Menu(MenuState::Shop), despawn_shop_screen)
+            // MENU - MUTATORS
+            .add_enter_system(AppState::Menu(MenuState::Mutators), spawn_mutators_screen)
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(AppState::Menu(MenuState::Mutators))
+                    .with_system(update_mutators_screen)
+                    .into(),
+            )
+            .add_exit_system(AppState::Menu(MenuState::Mutators), despawn_mutators_screen);
+    }
+}
 
 #[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum UiElement {
     GameReadyLabel,
     GameScore,
+    ScorePopup,
+    RunCoins,
+    PowerUpTimers,
+    GameOverSummary,
+    Toast,
+    StatsScreen,
+    CharacterSelectScreen,
+    ShopScreen,
+    MutatorsScreen,
 }
 trait UniqueIdentifierValue {}
 impl UniqueIdentifierValue for UiElement {}
 
-pub fn spawn_game_ready_label(commands: Commands, game_assets: Res<GameAssets>) {
+#[derive(Component)]
+pub struct Toast {
+    timer: Timer,
+    slot: usize,
+}
+
+/// Preview of the roster character at this index on the character select screen.
+#[derive(Component)]
+pub struct CharacterPreview(usize);
+
+#[derive(Component)]
+pub struct CharacterSelectLabel;
+
+#[derive(Component)]
+pub struct RunCoinsLabel;
+
+/// Line of the catalog item at this index in the shop.
+#[derive(Component)]
+pub struct ShopLine(usize);
+
+#[derive(Component)]
+pub struct ShopBalanceLabel;
+
+/// Line of the mutator at this index in [`Mutator::ALL`] on the mutators screen.
+#[derive(Component)]
+pub struct MutatorLine(usize);
+
+#[derive(Component)]
+pub struct MutatorsLeaderboardLabel;
+
+/// Time left of the active power-up with this id.
+#[derive(Component)]
+pub struct PowerUpTimerBar(&'static str);
+
+pub fn spawn_game_ready_label(
+    commands: Commands,
+    game_assets: Res<GameAssets>,
+    mutators: Res<Mutators>,
+) {
+    let mutators_line = if mutators.is_standard() {
+        String::new()
+    } else {
+        format!("\n{}", mutators.label())
+    };
     spawn_ui_text(
         commands,
         SpawnUiTextArgs {
             ui_element: UiElement::GameReadyLabel,
-            text: Text::from_section(
-                "Ready to play",
-                TextStyle {
-                    font: game_assets.font.clone(),
-                    font_size: 22.0,
-                    color: Color::WHITE,
-                },
-            )
+            text: Text::from_sections([
+                TextSection::new(
+                    "Ready to play\n",
+                    TextStyle {
+                        font: game_assets.font.clone(),
+                        font_size: 22.0,
+                        color: Color::WHITE,
+                    },
+                ),
+                TextSection::new(
+                    READY_HINTS,
+                    TextStyle {
+                        font: game_assets.font.clone(),
+                        font_size: 8.0,
+                        color: Color::WHITE,
+                    },
+                ),
+                TextSection::new(
+                    mutators_line,
+                    TextStyle {
+                        font: game_assets.font.clone(),
+                        font_size: 8.0,
+                        color: Color::WHITE,
+                    },
+                ),
+            ])
             .with_alignment(TextAlignment::CENTER),
             position: Vec3::new(0.0, 40.0, 0.0),
         },
@@ -35,28 +272,544 @@ pub fn spawn_game_ready_label(commands: Commands, game_assets: Res<GameAssets>)
 }
 
 pub fn spawn_game_score(
-    commands: Commands,
+    mut commands: Commands,
     game_assets: Res<GameAssets>,
     player_score: Res<PlayerScore>,
 ) {
-    spawn_ui_text(
-        commands,
-        SpawnUiTextArgs {
-            ui_element: UiElement::GameScore,
+    commands
+        .spawn((
+            SpatialBundle::from_transform(Transform::from_translation(Vec3::new(
+                0.0,
+                GAME_HEIGHT / 2.0 - 20.0,
+                DIGITS_Z,
+            ))),
+            UiElement::GameScore,
+        ))
+        .with_children(|parent| {
+            spawn_digit_sprites(
+                parent,
+                game_assets.digits_atlas.clone(),
+                &player_score.value.to_string(),
+            );
+        });
+}
+
+pub fn spawn_run_coins_label(mut commands: Commands, game_assets: Res<GameAssets>) {
+    commands.spawn((
+        Text2dBundle {
             text: Text::from_section(
-                player_score.value.to_string(),
+                "0 coins",
                 TextStyle {
                     font: game_assets.font.clone(),
-                    font_size: 22.0,
+                    font_size: 8.0,
                     color: Color::WHITE,
                 },
             )
-            .with_alignment(TextAlignment::TOP_CENTER),
-            position: Vec3::new(0.0, GAME_HEIGHT / 2.0 - 11.0, 0.0),
+            .with_alignment(TextAlignment::CENTER_RIGHT),
+            transform: Transform::from_xyz(
+                GAME_WIDTH / 2.0 - RUN_COINS_MARGIN,
+                GAME_HEIGHT / 2.0 - RUN_COINS_MARGIN - 4.0,
+                DIGITS_Z,
+            ),
+            ..Default::default()
         },
+        RunCoinsLabel,
+        UiElement::RunCoins,
+    ));
+}
+
+pub fn update_run_coins_label(
+    mut q_label: Query<&mut Text, With<RunCoinsLabel>>,
+    run_coins: Res<RunCoins>,
+) {
+    if !run_coins.is_changed() {
+        return;
+    }
+    for mut text in q_label.iter_mut() {
+        text.sections[0].value = format!("{} coins", run_coins.value);
+    }
+}
+
+pub fn spawn_power_up_timers(mut commands: Commands) {
+    commands.spawn((
+        SpatialBundle::from_transform(Transform::from_translation(Vec3::new(
+            -GAME_WIDTH / 2.0 + POWER_UP_TIMERS_MARGIN,
+            GAME_HEIGHT / 2.0 - POWER_UP_TIMERS_MARGIN - POWER_UP_TIMER_ROW_HEIGHT / 2.0,
+            DIGITS_Z,
+        ))),
+        UiElement::PowerUpTimers,
+    ));
+}
+
+pub fn spawn_game_over_summary(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    history: Res<RunHistory>,
+) {
+    let (score, medal, death_cause, mutators) = history
+        .last_run()
+        .map(|r| (r.score, r.medal, r.stats.death_cause, r.mutators.clone()))
+        .unwrap_or((0, None, None, Mutators::default()));
+    let previous_best = history.previous_best_score();
+    let best_label = if score > previous_best {
+        format!("New best {}", score)
+    } else {
+        format!("Best {}", previous_best)
+    };
+    let text_style = |font_size: f32| TextStyle {
+        font: game_assets.font.clone(),
+        font_size,
+        color: Color::WHITE,
+    };
+
+    commands
+        .spawn((
+            SpatialBundle::from_transform(Transform::from_translation(Vec3::new(
+                0.0,
+                -GAME_HEIGHT,
+                SUMMARY_Z,
+            ))),
+            Tween::new(SUMMARY_ENTER_DURATION, Ease::BackOut)
+                .with(TweenTarget::Offset {
+                    by: Vec3::new(0.0, GAME_HEIGHT, 0.0),
+                })
+                .on_completion(TweenCompletion::Remove),
+            UiElement::GameOverSummary,
+        ))
+        .with_children(|parent| {
+            parent.spawn(SpriteBundle {
+                sprite: Sprite {
+                    color: SUMMARY_PANEL_COLOR,
+                    custom_size: Some(SUMMARY_SIZE),
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+            parent.spawn(Text2dBundle {
+                text: Text::from_section("Game over", text_style(16.0))
+                    .with_alignment(TextAlignment::CENTER),
+                transform: Transform::from_xyz(0.0, 42.0, 1.0),
+                ..Default::default()
+            });
+            if let Some(death_cause) = death_cause {
+                parent.spawn(Text2dBundle {
+                    text: Text::from_section(death_cause.label(), text_style(8.0))
+                        .with_alignment(TextAlignment::CENTER),
+                    transform: Transform::from_xyz(0.0, 28.0, 1.0),
+                    ..Default::default()
+                });
+            }
+            if let Some(medal) = medal {
+                parent.spawn(SpriteSheetBundle {
+                    texture_atlas: game_assets.medals_atlas.clone(),
+                    sprite: TextureAtlasSprite::new(medal.atlas_index()),
+                    transform: Transform::from_xyz(-62.0, -4.0, 1.0).with_scale(Vec3::splat(2.0)),
+                    ..Default::default()
+                });
+                parent.spawn(Text2dBundle {
+                    text: Text::from_section(medal.label(), text_style(8.0))
+                        .with_alignment(TextAlignment::CENTER),
+                    transform: Transform::from_xyz(-62.0, -36.0, 1.0),
+                    ..Default::default()
+                });
+            }
+            parent.spawn(Text2dBundle {
+                text: Text::from_section(format!("Score {}", score), text_style(11.0))
+                    .with_alignment(TextAlignment::CENTER_LEFT),
+                transform: Transform::from_xyz(-22.0, 10.0, 1.0),
+                ..Default::default()
+            });
+            parent.spawn(Text2dBundle {
+                text: Text::from_section(best_label, text_style(11.0))
+                    .with_alignment(TextAlignment::CENTER_LEFT),
+                transform: Transform::from_xyz(-22.0, -12.0, 1.0),
+                ..Default::default()
+            });
+            // bests are kept per category
+            if !mutators.is_standard() {
+                parent.spawn(Text2dBundle {
+                    text: Text::from_section(mutators.label(), text_style(8.0))
+                        .with_alignment(TextAlignment::CENTER_LEFT),
+                    transform: Transform::from_xyz(-22.0, -28.0, 1.0),
+                    ..Default::default()
+                });
+            }
+            parent.spawn(Text2dBundle {
+                text: Text::from_section("Press space to retry", text_style(8.0))
+                    .with_alignment(TextAlignment::CENTER),
+                transform: Transform::from_xyz(0.0, -50.0, 1.0),
+                ..Default::default()
+            });
+        });
+}
+
+pub fn spawn_stats_screen(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    lifetime_stats: Res<LifetimeStats>,
+) {
+    let average_score = if lifetime_stats.runs > 0 {
+        lifetime_stats.total_score as f32 / lifetime_stats.runs as f32
+    } else {
+        0.0
+    };
+    let lines = [
+        format!("{:<14}{}", "Runs", lifetime_stats.runs),
+        format!("{:<14}{}", "Best score", lifetime_stats.best_score),
+        format!("{:<14}{:.1}", "Average", average_score),
+        format!("{:<14}{}", "Flaps", lifetime_stats.flaps),
+        format!("{:<14}{:.0}s", "Airtime", lifetime_stats.airtime),
+        format!("{:<14}{:.0}px", "Distance", lifetime_stats.distance),
+        format!("{:<14}{:.0}px/s", "Max speed", lifetime_stats.max_speed),
+        match lifetime_stats.closest_near_miss {
+            Some(near_miss) => format!("{:<14}{:.1}px", "Closest miss", near_miss),
+            None => format!("{:<14}-", "Closest miss"),
+        },
+        format!(
+            "{:<14}{} top {} ground {} pipe {} hazard",
+            "Deaths",
+            lifetime_stats.deaths_top_bound,
+            lifetime_stats.deaths_ground,
+            lifetime_stats.deaths_pipe,
+            lifetime_stats.deaths_hazard
+        ),
+    ];
+    spawn_panel(
+        &mut commands,
+        game_assets.font.clone(),
+        UiElement::StatsScreen,
+        "Lifetime stats",
+        &lines,
     );
 }
 
+pub fn spawn_character_select_screen(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    roster: Res<CharacterRoster>,
+) {
+    let characters = roster.characters();
+    let width = (characters.len() as f32 * CHARACTER_PREVIEW_SPACING + 32.0).max(PANEL_WIDTH);
+    let start_x = -(characters.len() as f32 - 1.0) * CHARACTER_PREVIEW_SPACING / 2.0;
+    let text_style = |font_size: f32| TextStyle {
+        font: game_assets.font.clone(),
+        font_size,
+        color: Color::WHITE,
+    };
+    commands
+        .spawn((
+            SpatialBundle::from_transform(Transform::from_xyz(0.0, 0.0, SUMMARY_Z)),
+            UiElement::CharacterSelectScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(SpriteBundle {
+                sprite: Sprite {
+                    color: SUMMARY_PANEL_COLOR,
+                    custom_size: Some(Vec2::new(width, CHARACTER_SELECT_HEIGHT)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+            parent.spawn(Text2dBundle {
+                text: Text::from_section("Character", text_style(11.0))
+                    .with_alignment(TextAlignment::CENTER),
+                transform: Transform::from_xyz(0.0, CHARACTER_SELECT_HEIGHT / 2.0 - 16.0, 1.0),
+                ..Default::default()
+            });
+            for (i, character) in characters.iter().enumerate() {
+                let animator = Animator::new(character.clips.clone(), CLIP_IDLE);
+                parent.spawn((
+                    SpriteSheetBundle {
+                        texture_atlas: game_assets.characters_atlas.clone(),
+                        sprite: TextureAtlasSprite::new(animator.atlas_index().unwrap_or(0)),
+                        transform: Transform::from_xyz(
+                            start_x + i as f32 * CHARACTER_PREVIEW_SPACING,
+                            14.0,
+                            1.0,
+                        )
+                        .with_scale(Vec3::splat(CHARACTER_PREVIEW_SCALE)),
+                        ..Default::default()
+                    },
+                    animator,
+                    CharacterPreview(i),
+                ));
+            }
+            parent.spawn((
+                Text2dBundle {
+                    text: Text::from_sections([
+                        TextSection::new("", text_style(11.0)),
+                        TextSection::new("", text_style(8.0)),
+                    ])
+                    .with_alignment(TextAlignment::CENTER),
+                    transform: Transform::from_xyz(0.0, -34.0, 1.0),
+                    ..Default::default()
+                },
+                CharacterSelectLabel,
+            ));
+            parent.spawn(Text2dBundle {
+                text: Text::from_section(CHARACTER_SELECT_HINTS, text_style(8.0))
+                    .with_alignment(TextAlignment::CENTER),
+                transform: Transform::from_xyz(0.0, -CHARACTER_SELECT_HEIGHT / 2.0 + 12.0, 1.0),
+                ..Default::default()
+            });
+        });
+}
+
+pub fn update_character_select_screen(
+    mut q_previews: Query<(&CharacterPreview, &mut Transform, &mut TextureAtlasSprite)>,
+    mut q_label: Query<&mut Text, With<CharacterSelectLabel>>,
+    cursor: Res<CharacterSelectCursor>,
+    roster: Res<CharacterRoster>,
+    catalog: Res<ShopCatalog>,
+    wallet: Res<Wallet>,
+) {
+    if !cursor.is_changed() {
+        return;
+    }
+    let locked_price = |index: usize| {
+        roster
+            .characters()
+            .get(index)
+            .and_then(|character| catalog.locked_price(&character.id, &wallet))
+    };
+    for (preview, mut t, mut sprite) in q_previews.iter_mut() {
+        let selected = preview.0 == cursor.0;
+        // locked characters show as silhouettes
+        sprite.color = if locked_price(preview.0).is_some() {
+            CHARACTER_LOCKED_COLOR
+        } else {
+            Color::WHITE
+        };
+        t.scale = Vec3::splat(if selected {
+            CHARACTER_PREVIEW_SELECTED_SCALE
+        } else {
+            CHARACTER_PREVIEW_SCALE
+        });
+        sprite.color.set_a(if selected {
+            1.0
+        } else {
+            CHARACTER_PREVIEW_DIMMED_ALPHA
+        });
+    }
+    if let (Ok(mut text), Some(character)) =
+        (q_label.get_single_mut(), roster.characters().get(cursor.0))
+    {
+        text.sections[0].value = format!("{}\n", character.name);
+        text.sections[1].value = match locked_price(cursor.0) {
+            Some(price) => format!("{} coins in the shop", price),
+            None => format!(
+                "gravity {:.2}  jump {:.0}",
+                character.gravity_scale(),
+                character.jump_impulse()
+            ),
+        };
+    }
+}
+
+pub fn spawn_shop_screen(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    catalog: Res<ShopCatalog>,
+) {
+    let count = catalog.items().len();
+    let height = 72.0 + count as f32 * PANEL_LINE_HEIGHT;
+    let text_style = |font_size: f32| TextStyle {
+        font: game_assets.font.clone(),
+        font_size,
+        color: Color::WHITE,
+    };
+    commands
+        .spawn((
+            SpatialBundle::from_transform(Transform::from_xyz(0.0, 0.0, SUMMARY_Z)),
+            UiElement::ShopScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(SpriteBundle {
+                sprite: Sprite {
+                    color: SUMMARY_PANEL_COLOR,
+                    custom_size: Some(Vec2::new(PANEL_WIDTH, height)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+            parent.spawn(Text2dBundle {
+                text: Text::from_section("Shop", text_style(11.0))
+                    .with_alignment(TextAlignment::CENTER),
+                transform: Transform::from_xyz(0.0, height / 2.0 - 16.0, 1.0),
+                ..Default::default()
+            });
+            parent.spawn((
+                Text2dBundle {
+                    text: Text::from_section("", text_style(8.0))
+                        .with_alignment(TextAlignment::CENTER),
+                    transform: Transform::from_xyz(0.0, height / 2.0 - 32.0, 1.0),
+                    ..Default::default()
+                },
+                ShopBalanceLabel,
+            ));
+            for i in 0..count {
+                parent.spawn((
+                    Text2dBundle {
+                        text: Text::from_section("", text_style(8.0))
+                            .with_alignment(TextAlignment::CENTER_LEFT),
+                        transform: Transform::from_xyz(
+                            -PANEL_WIDTH / 2.0 + 12.0,
+                            height / 2.0 - 50.0 - i as f32 * PANEL_LINE_HEIGHT,
+                            1.0,
+                        ),
+                        ..Default::default()
+                    },
+                    ShopLine(i),
+                ));
+            }
+            parent.spawn(Text2dBundle {
+                text: Text::from_section(SHOP_HINTS, text_style(8.0))
+                    .with_alignment(TextAlignment::CENTER),
+                transform: Transform::from_xyz(0.0, -height / 2.0 + 12.0, 1.0),
+                ..Default::default()
+            });
+        });
+}
+
+pub fn update_shop_screen(
+    mut q_lines: Query<(&ShopLine, &mut Text), Without<ShopBalanceLabel>>,
+    mut q_balance: Query<&mut Text, With<ShopBalanceLabel>>,
+    cursor: Res<ShopCursor>,
+    catalog: Res<ShopCatalog>,
+    wallet: Res<Wallet>,
+    loadout: Res<Loadout>,
+    selected_character: Res<SelectedCharacter>,
+) {
+    if !(cursor.is_changed()
+        || wallet.is_changed()
+        || loadout.is_changed()
+        || selected_character.is_changed())
+    {
+        return;
+    }
+    if let Ok(mut text) = q_balance.get_single_mut() {
+        text.sections[0].value = format!("{} coins", wallet.coins);
+    }
+    for (line, mut text) in q_lines.iter_mut() {
+        if let Some(item) = catalog.items().get(line.0) {
+            let equipped = match item.kind {
+                ShopItemKind::Character => selected_character.id == item.id,
+                ShopItemKind::Trail { .. } => loadout.trail.as_deref() == Some(item.id.as_str()),
+                ShopItemKind::Background { .. } => {
+                    loadout.background.as_deref() == Some(item.id.as_str())
+                }
+            };
+            let status = if equipped {
+                "equipped".to_string()
+            } else if wallet.owns(&item.id) || item.price == 0 {
+                "owned".to_string()
+            } else {
+                format!("{} coins", item.price)
+            };
+            let marker = if line.0 == cursor.0 { ">" } else { " " };
+            text.sections[0].value = format!("{} {:<20}{}", marker, item.name, status);
+        }
+    }
+}
+
+pub fn spawn_mutators_screen(mut commands: Commands, game_assets: Res<GameAssets>) {
+    let text_style = |font_size: f32| TextStyle {
+        font: game_assets.font.clone(),
+        font_size,
+        color: Color::WHITE,
+    };
+    let top = MUTATORS_HEIGHT / 2.0;
+    commands
+        .spawn((
+            SpatialBundle::from_transform(Transform::from_xyz(0.0, 0.0, SUMMARY_Z)),
+            UiElement::MutatorsScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(SpriteBundle {
+                sprite: Sprite {
+                    color: SUMMARY_PANEL_COLOR,
+                    custom_size: Some(Vec2::new(PANEL_WIDTH, MUTATORS_HEIGHT)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+            parent.spawn(Text2dBundle {
+                text: Text::from_section("Mutators", text_style(11.0))
+                    .with_alignment(TextAlignment::CENTER),
+                transform: Transform::from_xyz(0.0, top - 16.0, 1.0),
+                ..Default::default()
+            });
+            for i in 0..Mutator::ALL.len() {
+                parent.spawn((
+                    Text2dBundle {
+                        text: Text::from_section("", text_style(8.0))
+                            .with_alignment(TextAlignment::CENTER_LEFT),
+                        transform: Transform::from_xyz(
+                            -PANEL_WIDTH / 2.0 + 12.0,
+                            top - 36.0 - i as f32 * PANEL_LINE_HEIGHT,
+                            1.0,
+                        ),
+                        ..Default::default()
+                    },
+                    MutatorLine(i),
+                ));
+            }
+            parent.spawn((
+                Text2dBundle {
+                    text: Text::from_section("", text_style(8.0))
+                        .with_alignment(TextAlignment::TOP_CENTER),
+                    transform: Transform::from_xyz(
+                        0.0,
+                        top - 36.0 - Mutator::ALL.len() as f32 * PANEL_LINE_HEIGHT,
+                        1.0,
+                    ),
+                    ..Default::default()
+                },
+                MutatorsLeaderboardLabel,
+            ));
+            parent.spawn(Text2dBundle {
+                text: Text::from_section(MUTATORS_HINTS, text_style(8.0))
+                    .with_alignment(TextAlignment::CENTER),
+                transform: Transform::from_xyz(0.0, -top + 12.0, 1.0),
+                ..Default::default()
+            });
+        });
+}
+
+pub fn update_mutators_screen(
+    mut q_lines: Query<(&MutatorLine, &mut Text), Without<MutatorsLeaderboardLabel>>,
+    mut q_leaderboard: Query<&mut Text, With<MutatorsLeaderboardLabel>>,
+    cursor: Res<MutatorCursor>,
+    mutators: Res<Mutators>,
+    history: Res<RunHistory>,
+) {
+    if !(cursor.is_changed() || mutators.is_changed()) {
+        return;
+    }
+    for (line, mut text) in q_lines.iter_mut() {
+        if let Some(&mutator) = Mutator::ALL.get(line.0) {
+            let marker = if line.0 == cursor.0 { ">" } else { " " };
+            let check = if mutators.is_active(mutator) {
+                "x"
+            } else {
+                " "
+            };
+            text.sections[0].value = format!("{} [{}] {}", marker, check, mutator.label());
+        }
+    }
+    if let Ok(mut text) = q_leaderboard.get_single_mut() {
+        let mut lines = vec![format!("Best runs  {}", mutators.label())];
+        let leaderboard = history.leaderboard(&mutators);
+        if leaderboard.is_empty() {
+            lines.push("no run yet".to_string());
+        }
+        for (rank, run) in leaderboard.iter().enumerate() {
+            lines.push(format!("{}. {}", rank + 1, run.score));
+        }
+        text.sections[0].value = lines.join("\n");
+    }
+}
+
 pub fn despawn_game_ready_label(commands: Commands, q_element: Query<(Entity, &UiElement)>) {
     despawn_ui_element_entities(commands, UiElement::GameReadyLabel, q_element);
 }
@@ -65,6 +818,54 @@ pub fn despawn_game_score(commands: Commands, q_element: Query<(Entity, &UiEleme
     despawn_ui_element_entities(commands, UiElement::GameScore, q_element);
 }
 
+pub fn despawn_game_over_summary(commands: Commands, q_element: Query<(Entity, &UiElement)>) {
+    despawn_ui_element_entities(commands, UiElement::GameOverSummary, q_element);
+}
+
+pub fn despawn_stats_screen(commands: Commands, q_element: Query<(Entity, &UiElement)>) {
+    despawn_ui_element_entities(commands, UiElement::StatsScreen, q_element);
+}
+
+pub fn despawn_character_select_screen(commands: Commands, q_element: Query<(Entity, &UiElement)>) {
+    despawn_ui_element_entities(commands, UiElement::CharacterSelectScreen, q_element);
+}
+
+pub fn despawn_score_popups(commands: Commands, q_element: Query<(Entity, &UiElement)>) {
+    despawn_ui_element_entities(commands, UiElement::ScorePopup, q_element);
+}
+
+pub fn despawn_run_coins_label(commands: Commands, q_element: Query<(Entity, &UiElement)>) {
+    despawn_ui_element_entities(commands, UiElement::RunCoins, q_element);
+}
+
+pub fn despawn_shop_screen(commands: Commands, q_element: Query<(Entity, &UiElement)>) {
+    despawn_ui_element_entities(commands, UiElement::ShopScreen, q_element);
+}
+
+pub fn despawn_mutators_screen(commands: Commands, q_element: Query<(Entity, &UiElement)>) {
+    despawn_ui_element_entities(commands, UiElement::MutatorsScreen, q_element);
+}
+
+pub fn despawn_power_up_timers(commands: Commands, q_element: Query<(Entity, &UiElement)>) {
+    despawn_ui_element_entities(commands, UiElement::PowerUpTimers, q_element);
+}
+
+/// Keeps the ui readable when the world is shown mirrored. Score popups stay where they were
+/// scored, the rest is laid out on the screen.
+pub fn mark_readable_ui_elements(
+    mut commands: Commands,
+    q_added: Query<(Entity, &UiElement), Added<UiElement>>,
+) {
+    for (e, &ui) in q_added.iter() {
+        let readable = if ui == UiElement::ScorePopup {
+            Readable::in_place()
+        } else {
+            Readable::screen_space()
+        };
+        commands.entity(e).insert(readable);
+    }
+}
+
 pub fn spawn_ui_text(mut commands: Commands, args: SpawnUiTextArgs) {
     commands.spawn((
         Text2dBundle {
@@ -90,14 +891,33 @@ pub fn despawn_ui_element_entities(
 }
 
 pub fn update_player_score_label(
+    mut commands: Commands,
     player_score: Res<PlayerScore>,
-    mut q_elements: Query<(&UiElement, &mut Text)>,
+    game_assets: Res<GameAssets>,
+    q_elements: Query<(Entity, &UiElement)>,
 ) {
     if player_score.is_changed() {
-        for (ui, mut text) in q_elements.iter_mut() {
+        for (e, ui) in q_elements.iter() {
             match ui {
                 UiElement::GameScore => {
-                    text.sections.first_mut().unwrap().value = player_score.value.to_string();
+                    commands
+                        .entity(e)
+                        .despawn_descendants()
+                        .insert(
+                            Tween::new(SCORE_POP_DURATION / 2.0, Ease::QuadOut)
+                                .with(TweenTarget::Scale {
+                                    start: Vec3::ONE,
+                                    end: Vec3::splat(1.0 + SCORE_POP_SCALE),
+                                })
+                                .with_mode(TweenMode::Yoyo),
+                        )
+                        .with_children(|parent| {
+                            spawn_digit_sprites(
+                                parent,
+                                game_assets.digits_atlas.clone(),
+                                &player_score.value.to_string(),
+                            );
+                        });
                 }
                 _ => {}
             }
@@ -105,6 +925,291 @@ pub fn update_player_score_label(
     }
 }
 
+pub fn spawn_score_popup(
+    mut commands: Commands,
+    mut ev_game: EventReader<GameEvent>,
+    game_assets: Res<GameAssets>,
+) {
+    for ev in ev_game.iter() {
+        if let GameEvent::PlayerPassedAnOpening {
+            opening_position, ..
+        } = ev
+        {
+            commands
+                .spawn((
+                    SpatialBundle::from_transform(Transform::from_translation(
+                        opening_position.extend(DIGITS_Z),
+                    )),
+                    HorizontalMove { factor: 1.0 },
+                    Tween::new(SCORE_POPUP_DURATION, Ease::CubicOut)
+                        .with(TweenTarget::Offset {
+                            by: Vec3::new(0.0, SCORE_POPUP_RISE, 0.0),
+                        })
+                        .on_completion(TweenCompletion::Despawn),
+                    UiElement::ScorePopup,
+                ))
+                .with_children(|parent| {
+                    spawn_glyph_sprites(
+                        parent,
+                        game_assets.digits_atlas.clone(),
+                        &[DIGIT_PLUS_INDEX, 1],
+                        Some(Tween::new(SCORE_POPUP_DURATION, Ease::QuadIn).with(
+                            TweenTarget::Alpha {
+                                start: 1.0,
+                                end: 0.0,
+                            },
+                        )),
+                    );
+                });
+        }
+    }
+}
+
+/// Lists the active power-ups, each one with a bar shrinking as its time runs out. The rows are
+/// only rebuilt when a power-up starts or ends.
+pub fn update_power_up_timers(
+    mut commands: Commands,
+    mut q_bars: Query<(&PowerUpTimerBar, &mut Sprite)>,
+    active: Res<ActivePowerUps>,
+    registry: Res<PowerUpRegistry>,
+    game_assets: Res<GameAssets>,
+    q_elements: Query<(Entity, &UiElement)>,
+) {
+    if !active.is_changed() {
+        return;
+    }
+    let bar_size = |remaining: f32, seconds: f32| {
+        Vec2::new(
+            POWER_UP_TIMER_BAR_SIZE.x * (remaining / seconds.max(f32::EPSILON)).clamp(0.0, 1.0),
+            POWER_UP_TIMER_BAR_SIZE.y,
+        )
+    };
+
+    let mut shown: Vec<&str> = q_bars.iter().map(|(bar, _)| bar.0).collect();
+    shown.sort_unstable();
+    if shown.iter().copied().eq(active.iter().map(|(id, _)| id)) {
+        for (bar, mut sprite) in q_bars.iter_mut() {
+            if let Some((_, timer)) = active.iter().find(|(id, _)| *id == bar.0) {
+                sprite.custom_size = Some(bar_size(timer.remaining, timer.seconds));
+            }
+        }
+        return;
+    }
+
+    for (e, ui) in q_elements.iter() {
+        if *ui != UiElement::PowerUpTimers {
+            continue;
+        }
+        commands
+            .entity(e)
+            .despawn_descendants()
+            .with_children(|parent| {
+                let rows = active
+                    .iter()
+                    .filter_map(|(id, timer)| registry.get(id).map(|p| (p, timer)));
+                for (i, (power_up, timer)) in rows.enumerate() {
+                    let y = -(i as f32) * POWER_UP_TIMER_ROW_HEIGHT;
+                    parent.spawn(Text2dBundle {
+                        text: Text::from_section(
+                            power_up.name,
+                            TextStyle {
+                                font: game_assets.font.clone(),
+                                font_size: 8.0,
+                                color: power_up.color,
+                            },
+                        )
+                        .with_alignment(TextAlignment::CENTER_LEFT),
+                        transform: Transform::from_xyz(0.0, y, 0.0),
+                        ..Default::default()
+                    });
+                    parent.spawn((
+                        SpriteBundle {
+                            sprite: Sprite {
+                                color: power_up.color,
+                                custom_size: Some(bar_size(timer.remaining, timer.seconds)),
+                                anchor: Anchor::CenterLeft,
+                                ..Default::default()
+                            },
+                            transform: Transform::from_xyz(POWER_UP_TIMER_BAR_X, y, 0.0),
+                            ..Default::default()
+                        },
+                        PowerUpTimerBar(power_up.id),
+                    ));
+                }
+            });
+    }
+}
+
+/// Spawns a centered panel with a title and left aligned lines of text.
+pub fn spawn_panel(
+    commands: &mut Commands,
+    font: Handle<Font>,
+    ui_element: UiElement,
+    title: &str,
+    lines: &[String],
+) {
+    let height = 48.0 + lines.len() as f32 * PANEL_LINE_HEIGHT;
+    let text_style = |font_size: f32| TextStyle {
+        font: font.clone(),
+        font_size,
+        color: Color::WHITE,
+    };
+    commands
+        .spawn((
+            SpatialBundle::from_transform(Transform::from_xyz(0.0, 0.0, SUMMARY_Z)),
+            ui_element,
+        ))
+        .with_children(|parent| {
+            parent.spawn(SpriteBundle {
+                sprite: Sprite {
+                    color: SUMMARY_PANEL_COLOR,
+                    custom_size: Some(Vec2::new(PANEL_WIDTH, height)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+            parent.spawn(Text2dBundle {
+                text: Text::from_section(title, text_style(11.0))
+                    .with_alignment(TextAlignment::CENTER),
+                transform: Transform::from_xyz(0.0, height / 2.0 - 16.0, 1.0),
+                ..Default::default()
+            });
+            for (i, line) in lines.iter().enumerate() {
+                parent.spawn(Text2dBundle {
+                    text: Text::from_section(line.as_str(), text_style(8.0))
+                        .with_alignment(TextAlignment::CENTER_LEFT),
+                    transform: Transform::from_xyz(
+                        -PANEL_WIDTH / 2.0 + 12.0,
+                        height / 2.0 - 38.0 - i as f32 * PANEL_LINE_HEIGHT,
+                        1.0,
+                    ),
+                    ..Default::default()
+                });
+            }
+        });
+}
+
+/// Spawns a notification sliding in the top right corner, `slot` stacks it below the
+/// notifications already on screen.
+pub fn spawn_toast(
+    commands: &mut Commands,
+    font: Handle<Font>,
+    title: &str,
+    message: &str,
+    slot: usize,
+) {
+    let slide_distance = TOAST_SIZE.x + TOAST_MARGIN * 2.0;
+    let position = Vec3::new(
+        GAME_WIDTH / 2.0 - TOAST_SIZE.x / 2.0 - TOAST_MARGIN + slide_distance,
+        GAME_HEIGHT / 2.0
+            - TOAST_SIZE.y / 2.0
+            - TOAST_MARGIN
+            - slot as f32 * (TOAST_SIZE.y + TOAST_MARGIN),
+        TOAST_Z,
+    );
+    let text_style = |font_size: f32| TextStyle {
+        font: font.clone(),
+        font_size,
+        color: Color::WHITE,
+    };
+    commands
+        .spawn((
+            SpatialBundle::from_transform(Transform::from_translation(position)),
+            Tween::new(TOAST_SLIDE_DURATION, Ease::QuadOut).with(TweenTarget::Offset {
+                by: Vec3::new(-slide_distance, 0.0, 0.0),
+            }),
+            Toast {
+                timer: Timer::from_seconds(TOAST_DURATION, TimerMode::Once),
+                slot,
+            },
+            UiElement::Toast,
+        ))
+        .with_children(|parent| {
+            parent.spawn(SpriteBundle {
+                sprite: Sprite {
+                    color: SUMMARY_PANEL_COLOR,
+                    custom_size: Some(TOAST_SIZE),
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+            parent.spawn(Text2dBundle {
+                text: Text::from_section(title, text_style(8.0))
+                    .with_alignment(TextAlignment::CENTER_LEFT),
+                transform: Transform::from_xyz(-TOAST_SIZE.x / 2.0 + 8.0, 6.0, 1.0),
+                ..Default::default()
+            });
+            parent.spawn(Text2dBundle {
+                text: Text::from_section(message, text_style(8.0))
+                    .with_alignment(TextAlignment::CENTER_LEFT),
+                transform: Transform::from_xyz(-TOAST_SIZE.x / 2.0 + 8.0, -7.0, 1.0),
+                ..Default::default()
+            });
+        });
+}
+
+/// Slots left free by the toasts shown, from the top of the screen down.
+pub fn free_toast_slots(q_toasts: &Query<&Toast>) -> impl Iterator<Item = usize> {
+    let taken: Vec<usize> = q_toasts.iter().map(|toast| toast.slot).collect();
+    (0..).filter(move |slot| !taken.contains(slot))
+}
+
+pub fn free_toast_slot(q_toasts: &Query<&Toast>) -> usize {
+    free_toast_slots(q_toasts).next().unwrap_or_default()
+}
+
+pub fn expire_toasts(
+    mut commands: Commands,
+    mut q_toasts: Query<(Entity, &mut Toast)>,
+    time: Res<Time>,
+) {
+    for (e, mut toast) in q_toasts.iter_mut() {
+        if toast.timer.tick(time.delta()).just_finished() {
+            commands.entity(e).insert(
+                Tween::new(TOAST_SLIDE_DURATION, Ease::QuadIn)
+                    .with(TweenTarget::Offset {
+                        by: Vec3::new(TOAST_SIZE.x + TOAST_MARGIN * 2.0, 0.0, 0.0),
+                    })
+                    .on_completion(TweenCompletion::Despawn),
+            );
+        }
+    }
+}
+
+fn spawn_digit_sprites(parent: &mut ChildBuilder, atlas: Handle<TextureAtlas>, digits: &str) {
+    let glyphs: Vec<usize> = digits
+        .chars()
+        .filter_map(|c| c.to_digit(10))
+        .map(|d| d as usize)
+        .collect();
+    spawn_glyph_sprites(parent, atlas, &glyphs, None);
+}
+
+fn spawn_glyph_sprites(
+    parent: &mut ChildBuilder,
+    atlas: Handle<TextureAtlas>,
+    glyphs: &[usize],
+    tween: Option<Tween>,
+) {
+    // glyphs are laid out centered on the parent
+    let start_x = -(glyphs.len() as f32 - 1.0) * DIGIT_ADVANCE / 2.0;
+    for (i, &glyph) in glyphs.iter().enumerate() {
+        let mut glyph_entity = parent.spawn(SpriteSheetBundle {
+            texture_atlas: atlas.clone(),
+            sprite: TextureAtlasSprite::new(glyph),
+            transform: Transform::from_translation(Vec3::new(
+                start_x + i as f32 * DIGIT_ADVANCE,
+                0.0,
+                0.0,
+            )),
+            ..Default::default()
+        });
+        if let Some(tween) = tween.as_ref() {
+            glyph_entity.insert(tween.clone());
+        }
+    }
+}
+
 pub struct SpawnUiTextArgs {
     ui_element: UiElement,
     text: Text,
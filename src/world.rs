@@ -1,17 +1,106 @@
 use bevy::{
-    prelude::{Commands, Component, Res, Transform, Vec3},
+    prelude::{
+        App, ClearColor, Color, Commands, Component, CoreStage, IntoSystemDescriptor, Plugin, Res,
+        Transform, Vec2, Vec3,
+    },
     sprite::{Anchor, Sprite, SpriteBundle},
+    time::TimeSystem,
     transform::TransformBundle,
 };
-use bevy_rapier2d::prelude::{Collider, CollisionGroups, GravityScale, LockedAxes, RigidBody};
+use iyes_loopless::prelude::{AppLooplessStateExt, ConditionSet, IntoConditionalSystem};
 
 use crate::{
+    app_states::{AppState, InGameState},
     assets::GameAssets,
-    consts::{COLLISION_GROUP_GAME_OVER, COLLISION_GROUP_PLAYER, GAME_HEIGHT, GAME_WIDTH},
-    game::HorizontalMove,
+    console::RegisterConsoleCommands,
+    consts::{
+        BASE_GAME_SPEED, COLLISION_GROUP_GAME_OVER, COLLISION_GROUP_PLAYER, GAME_HEIGHT,
+        GAME_WIDTH, GRAVITY,
+    },
+    events::{
+        dispatch_collision_events, send_run_ended_event, send_run_started_event, DeathCause,
+        GameEvent,
+    },
+    game::{
+        advance_game_tick, frame_advance_on_input, ground_buffer_swap,
+        handle_game_console_commands, move_game_elements_horizontal, reset_game_speed,
+        reset_game_tick, toggle_practice_mode_on_input, update_game_speed, update_game_time,
+        GameSpeed, GameTick, GameTime, HorizontalMove, PracticeMode, TimeScale,
+        GAME_CONSOLE_COMMANDS,
+    },
     obstacles::Obstacle,
+    physics::{Gravity, Hitbox, PhysicsBody},
+    schedule::GameStage,
+    transitions::no_screen_transition,
 };
 
+/// Color of the sky behind the ground, as rgb hex.
+pub const SKY_COLOR: &str = "dff6f5";
+
+/// The scrolling ground, the world bounds and the game pace, along with the collision dispatch
+/// turning physics contacts into [`GameEvent`]s.
+pub struct WorldPlugin;
+
+impl Plugin for WorldPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ClearColor(Color::hex(SKY_COLOR).unwrap()))
+            .insert_resource(GameSpeed {
+                factor: BASE_GAME_SPEED,
+            })
+            .insert_resource(Gravity(Vec2::new(0.0, GRAVITY)))
+            .init_resource::<GameTick>()
+            .init_resource::<TimeScale>()
+            .init_resource::<GameTime>()
+            .init_resource::<PracticeMode>()
+            .add_event::<GameEvent>()
+            .register_console_commands(GAME_CONSOLE_COMMANDS)
+            .add_system(handle_game_console_commands)
+            .add_system_to_stage(CoreStage::First, update_game_time.after(TimeSystem))
+            .add_system(frame_advance_on_input)
+            .add_system(
+                toggle_practice_mode_on_input
+                    .run_in_state(AppState::InGame(InGameState::ReadyToStart)),
+            )
+            .add_enter_system_set(
+                AppState::InGame(InGameState::Initialization),
+                ConditionSet::new().with_system(spawn_world_ground).into(),
+            )
+            .add_enter_system(AppState::InGame(InGameState::ReadyToStart), reset_game_tick)
+            .add_enter_system(
+                AppState::InGame(InGameState::ReadyToStart),
+                reset_game_speed,
+            )
+            .add_enter_system(
+                AppState::InGame(InGameState::Playing),
+                send_run_started_event,
+            )
+            .add_system(
+                advance_game_tick
+                    .run_in_state(AppState::InGame(InGameState::Playing))
+                    .run_if(no_screen_transition),
+            )
+            .add_system_set_to_stage(
+                GameStage::Movement,
+                ConditionSet::new()
+                    .run_in_state(AppState::InGame(InGameState::Playing))
+                    .run_if(no_screen_transition)
+                    .with_system(move_game_elements_horizontal)
+                    .with_system(update_game_speed)
+                    .with_system(ground_buffer_swap)
+                    .into(),
+            )
+            .add_system_set_to_stage(
+                GameStage::CollisionDispatch,
+                ConditionSet::new()
+                    .run_in_state(AppState::InGame(InGameState::Playing))
+                    .run_if(no_screen_transition)
+                    .with_system(dispatch_collision_events)
+                    .into(),
+            )
+            .add_exit_system(AppState::InGame(InGameState::Playing), send_run_ended_event);
+    }
+}
+
 #[derive(Component)]
 pub struct Ground;
 
@@ -60,16 +149,17 @@ pub fn spawn_world_ground(mut commands: Commands, game_assets: Res<GameAssets>)
     let collider_height = 200.0;
     let y_collider_offset = collider_height / 2.0 + y_world_offset;
     let bottom_collider_tiles_offset = 18.0 * 2.0; // 2 tiles of 18px each
+    let bound_hitbox = Hitbox::Rect {
+        half_extents: Vec2::new(GAME_WIDTH / 2.0, collider_height / 2.0),
+    };
 
     // spawn static top and down death colliders
     commands.spawn((
         TransformBundle::from(Transform::from_xyz(0.0, y_collider_offset, 0.0)),
-        Collider::cuboid(GAME_WIDTH / 2.0, collider_height / 2.0),
-        RigidBody::Fixed,
-        GravityScale(0.0),
-        LockedAxes::TRANSLATION_LOCKED | LockedAxes::ROTATION_LOCKED,
-        CollisionGroups::new(COLLISION_GROUP_GAME_OVER, COLLISION_GROUP_PLAYER),
+        PhysicsBody::fixed(bound_hitbox.clone())
+            .with_groups(COLLISION_GROUP_GAME_OVER, COLLISION_GROUP_PLAYER),
         Obstacle::GameOverStatic,
+        DeathCause::TopBound,
     ));
     commands.spawn((
         TransformBundle::from(Transform::from_xyz(
@@ -77,11 +167,9 @@ pub fn spawn_world_ground(mut commands: Commands, game_assets: Res<GameAssets>)
             -y_collider_offset + bottom_collider_tiles_offset,
             0.0,
         )),
-        Collider::cuboid(GAME_WIDTH / 2.0, collider_height / 2.0),
-        RigidBody::Fixed,
-        GravityScale(0.0),
-        LockedAxes::TRANSLATION_LOCKED | LockedAxes::ROTATION_LOCKED,
-        CollisionGroups::new(COLLISION_GROUP_GAME_OVER, COLLISION_GROUP_PLAYER),
+        PhysicsBody::fixed(bound_hitbox)
+            .with_groups(COLLISION_GROUP_GAME_OVER, COLLISION_GROUP_PLAYER),
         Obstacle::GameOverStatic,
+        DeathCause::Ground,
     ));
 }